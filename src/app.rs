@@ -1,99 +1,2573 @@
 use std::{
-    error::Error,
+    borrow::Cow,
+    cmp::Ordering as CmpOrdering,
+    collections::{hash_map::RandomState, HashMap, HashSet, VecDeque},
+    fs::{File, OpenOptions},
+    io::{LineWriter, Write as _},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
+use btleplug::api::{CharPropFlags, Peripheral as _};
 use ratatui::widgets::TableState;
-use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use regex::Regex;
+use tokio::{
+    sync::{
+        mpsc::{self, Receiver, Sender, UnboundedSender},
+        oneshot, watch,
+    },
+    task::JoinHandle,
+};
+use uuid::Uuid;
 
 use crate::{
-    scan::{bluetooth_scan, get_characteristics},
-    structs::{Characteristic, DeviceCsv, DeviceInfo},
+    cli::{Args, EvictionPolicy, HexFormat},
+    config::{
+        load_columns, load_dense_layout, load_favorites, load_keymap, save_dense_layout,
+        save_favorites,
+    },
+    discovery_log::discovery_log,
+    error::Error,
+    gatt_names::GATT_SERVICES,
+    keymap::Keymap,
+    scan::{
+        abandon_connection, bluetooth_scan, disconnect, find_peripheral, get_characteristics,
+        nus_terminal, read_characteristic, read_characteristics, subscribe_characteristic,
+        synthetic_scan, unsubscribe, watch_connection, write_characteristic,
+    },
+    status_server::status_server,
+    structs::{
+        manufacturer_data_csv, AdvertisementCsv, Characteristic, Column, ConnectionState,
+        DeviceCsv, DeviceFilter, DeviceInfo, DeviceJson, LogDirection, LogEntry, PropertyFilter,
+        StatusJson, StatusSnapshot, BATTERY_LEVEL_UUID, NUS_RX_UUID, NUS_SERVICE_UUID, NUS_TX_UUID,
+        TX_POWER_LEVEL_UUID,
+    },
+    utils::{
+        bluetooth_uuid, bytes_to_hex, device_information_label, estimate_distance, format_duration,
+        hex_to_bytes, normalize_uuid, service_name, short_uuid, signal_percent, uuid_to_u16,
+        DEVICE_INFORMATION_UUID,
+    },
 };
 
 pub enum DeviceData {
-    DeviceInfo(DeviceInfo),
-    #[allow(dead_code)]
-    Characteristics(Vec<Characteristic>),
-    Error(String),
+    DeviceInfo(Box<DeviceInfo>),
+    /// The characteristics discovered by the connection attempt with the given id.
+    Characteristics(u64, Vec<Characteristic>),
+    NusData(Vec<u8>),
+    CharacteristicValue(Uuid, Vec<u8>),
+    CharacteristicWritten(Uuid, Vec<u8>),
+    /// A value written to a characteristic, followed by the value read back from it.
+    WriteVerified(Uuid, Vec<u8>, Vec<u8>),
+    Disconnected(String),
+    /// A progress message for the message log.
+    Status(String),
+    /// The outcome of an automatic export, for the message log.
+    Checkpoint(Result<String, Error>),
+    /// The failure of the connection attempt with the given id.
+    ConnectError(u64, Error),
+    Error(Error),
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    Editing,
+}
+
+/// What the text typed in `InputMode::Editing` is used for.
+#[derive(PartialEq)]
+pub enum InputTarget {
+    NusTerminal,
+    GoTo,
+    Note,
+    ConnectAddress,
+    Write,
+    WriteVerify,
+    ScanService,
+    NameFilter,
+    LogFilter,
+}
+
+/// The panel that navigation keys apply to. Tab cycles through them in the order they are
+/// laid out, wrapping around from the message log back to the device table.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Focus {
+    Devices,
+    Details,
+    Log,
+}
+
+impl Focus {
+    /// Returns the panel focused after this one.
+    pub fn next(self) -> Self {
+        match self {
+            Focus::Devices => Focus::Details,
+            Focus::Details => Focus::Log,
+            Focus::Log => Focus::Devices,
+        }
+    }
+}
+
+/// The column the device table is sorted by. Ties are broken by name and then identifier, so
+/// the order is total and rows don't jitter between redraws.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Detected,
+    Name,
+    TxPower,
+    Rssi,
+}
+
+impl SortKey {
+    /// Returns the next key in the order the keys are cycled through.
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Detected => SortKey::Name,
+            SortKey::Name => SortKey::TxPower,
+            SortKey::TxPower => SortKey::Rssi,
+            SortKey::Rssi => SortKey::Detected,
+        }
+    }
+
+    /// Compares two devices by the key, then by name and identifier. Only the primary key is
+    /// reversed when sorting descending. `Detected` devices always compare equal, so that a
+    /// stable sort keeps them in the order they were found.
+    pub fn compare(self, a: &DeviceInfo, b: &DeviceInfo, ascending: bool) -> CmpOrdering {
+        let number = |value: &str| value.parse::<i16>().ok();
+        let primary = match self {
+            SortKey::Detected => return CmpOrdering::Equal,
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::TxPower => number(&a.tx_power).cmp(&number(&b.tx_power)),
+            SortKey::Rssi => number(&a.display_rssi()).cmp(&number(&b.display_rssi())),
+        };
+        let primary = if ascending {
+            primary
+        } else {
+            primary.reverse()
+        };
+        primary
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.id.cmp(&b.id))
+    }
 }
 
 #[allow(dead_code)]
 pub struct App {
-    pub rx: UnboundedReceiver<DeviceData>,
-    pub tx: UnboundedSender<DeviceData>,
+    pub rx: Receiver<DeviceData>,
+    pub tx: Sender<DeviceData>,
+    pub dropped_events: Arc<AtomicUsize>,
     pub loading_status: Arc<AtomicBool>,
     pub pause_status: Arc<AtomicBool>,
     pub table_state: TableState,
     pub devices: Vec<DeviceInfo>,
     pub inspect_view: bool,
     pub inspect_overlay_scroll: usize,
+    pub raw_properties_view: bool,
+    pub raw_properties_scroll: usize,
+    pub payload_view: bool,
+    pub payload_scroll: u16,
     pub selected_characteristics: Vec<Characteristic>,
+    pub property_filter: PropertyFilter,
+    /// The highlighted row of `visible_characteristics`.
+    pub characteristic_selected: usize,
+    /// The last value read from, or notified by, each characteristic of the inspected device.
+    pub char_values: HashMap<Uuid, Vec<u8>>,
+    /// When each value in `char_values` was read or notified.
+    pub char_values_at: HashMap<Uuid, Instant>,
+    /// How long a read value is reused before a read goes to the device again.
+    pub read_cache_ttl: Duration,
+    /// Read readable characteristics as they are highlighted in the inspect overlay.
+    pub auto_read: bool,
+    /// When the highlighted characteristic is due to be read, once the highlight settles.
+    pub auto_read_due: Option<Instant>,
+    /// Characteristics written without response, rather than with response by default.
+    pub write_without_response: HashSet<Uuid>,
+    /// Ends the subscription to a characteristic of the inspected device when dropped.
+    pub notify_stops: HashMap<Uuid, oneshot::Sender<()>>,
     pub frame_count: usize,
+    /// When the loading spinner last advanced.
+    pub spinner_ticked_at: Instant,
     pub is_loading: bool,
     pub error_view: bool,
     pub error_message: String,
+    pub input_mode: InputMode,
+    pub input_buffer: String,
+    pub input_target: InputTarget,
+    pub inspected_device: Option<DeviceInfo>,
+    pub connect_timeout: Duration,
+    /// Ask before quitting while devices are connected.
+    pub confirm_quit: bool,
+    pub quit_confirm_view: bool,
+    /// How long to wait for a Bluetooth adapter to appear before giving up on scanning.
+    pub adapter_timeout: Duration,
+    /// When the pending connection attempt started.
+    pub connect_started: Option<Instant>,
+    /// Identifies the connection attempt whose results are awaited. Results carrying another
+    /// id are left over from an attempt that was cancelled since.
+    pub connect_attempt: u64,
+    /// The device last connected to, for reconnecting to it.
+    pub last_connected: Option<DeviceInfo>,
+    pub nus_view: bool,
+    pub nus_lines: Vec<String>,
+    pub nus_line_open: bool,
+    pub nus_sender: Option<UnboundedSender<String>>,
+    pub connections: HashMap<String, ConnectionState>,
+    pub connections_view: bool,
+    pub connections_selected: usize,
+    pub logs: Vec<LogEntry>,
+    pub notes: HashMap<String, String>,
+    pub favorites: HashSet<String>,
+    pub favorites_only: bool,
+    /// Only devices whose name matches are shown, when set.
+    pub name_filter: Option<Regex>,
+    /// The name filter applied when editing it started, restored when the edit is cancelled.
+    pub name_filter_before: Option<Regex>,
+    /// Why the name filter being typed doesn't compile, shown next to it.
+    pub name_filter_error: Option<String>,
+    /// Ring the bell and flash the device table when a device matching the filters is found.
+    pub alert_on_match: bool,
+    /// The devices already alerted on, so each one only alerts once.
+    pub alerted_ids: HashSet<String>,
+    /// Set until the bell for a newly found match is rung.
+    pub bell_pending: bool,
+    /// When the device table started flashing for a newly found match.
+    pub flash_at: Option<Instant>,
+    /// The id of the device being located, while in locate mode.
+    pub locate_id: Option<String>,
+    /// Beep faster as the located device gets closer.
+    pub locate_beep: bool,
+    pub locate_beeped_at: Instant,
+    /// The RSSI the current one is compared to, and when it was taken.
+    pub locate_reference: Option<(Instant, f32)>,
+    /// How much the RSSI changed over the last `LOCATE_TREND_INTERVAL`.
+    pub locate_trend: Option<f32>,
+    pub device_filters: Vec<DeviceFilter>,
+    /// The services scanned for, or every device when empty.
+    pub scan_services: Vec<Uuid>,
+    pub service_picker_view: bool,
+    /// The services listed by the service picker: the ones added by hand, then those assigned
+    /// by the Bluetooth SIG.
+    pub service_picker_services: Vec<Uuid>,
+    /// The services checked in the service picker, applied to `scan_services` on confirming.
+    pub service_picker_chosen: HashSet<Uuid>,
+    pub service_picker_selected: usize,
+    pub columns: Vec<Column>,
+    pub keymap: Keymap,
+    pub manufacturer_ascii: bool,
+    /// Show UUIDs assigned by the Bluetooth SIG in their short form, e.g. `0x2A37`.
+    pub short_uuids: bool,
+    /// Show the bitmask of characteristic properties next to their names.
+    pub props_hex: bool,
+    /// Kept open once used, since some platforms drop the contents along with the clipboard.
+    pub clipboard: Option<arboard::Clipboard>,
+    pub sort_key: SortKey,
+    pub focus: Focus,
+    /// Hides the details and the message log to give the device table the room, showing the
+    /// details in `details_view` on demand instead.
+    pub dense_layout: bool,
+    pub details_view: bool,
+    pub detail_scroll: usize,
+    /// How many entries the message log is scrolled back from the newest one.
+    pub log_scroll: usize,
+    /// Only log entries containing this text, ignoring case, are shown when it isn't empty.
+    pub log_filter: String,
+    pub max_devices: usize,
+    pub eviction_policy: EvictionPolicy,
+    pub demo: bool,
+    /// Redact addresses in exports, recordings, the discovery log and clipboard copies.
+    pub anonymize_exports: bool,
+    /// Redact addresses shown on screen as well.
+    pub anonymize_display: bool,
+    /// The random key of the session that redacted addresses are hashed with.
+    pub anonymize_key: RandomState,
+    pub last_seen: HashMap<String, Instant>,
+    /// The most recent times between advertisements of each device.
+    pub adv_intervals: HashMap<String, VecDeque<Duration>>,
+    /// When each cell of the device table last changed, by device id and column.
+    pub changed_at: HashMap<(String, Column), Instant>,
+    pub evicted_devices: usize,
+    /// How many advertisements updated a device already in the table instead of adding one.
+    pub duplicate_discoveries: usize,
+    /// Set while the device table shows `frozen_devices` instead of the devices as they update.
+    pub display_frozen: bool,
+    pub frozen_devices: Vec<DeviceInfo>,
+    pub sort_ascending: bool,
+    pub selected_ids: HashSet<String>,
+    pub path_loss_exponent: f32,
+    /// The weight of each new reading in the smoothed RSSI, or `None` to show the RSSI as is.
+    pub rssi_smoothing: Option<f32>,
+    pub reference_power: i16,
+    pub tick_rate: Duration,
+    pub loading_tick_rate: Duration,
+    pub idle_tick_rate: Duration,
+    pub last_activity: Instant,
+    /// Set when something shown on screen changed since the last redraw.
+    pub dirty: bool,
+    pub last_draw: Instant,
+    pub discovery_log: Option<UnboundedSender<AdvertisementCsv>>,
+    /// The task running `bluetooth_scan`, or `synthetic_scan` with `--demo`.
+    pub scan_task: Option<JoinHandle<()>>,
+    /// The task running `get_characteristics` for the connection attempt in progress.
+    pub connect_task: Option<JoinHandle<()>>,
+    /// Publishes snapshots to the status server, when serving with `--serve`.
+    pub status_tx: Option<watch::Sender<StatusSnapshot>>,
+    pub status_published_at: Instant,
+    /// How often the device list is exported automatically, with `--auto-export-interval`.
+    pub auto_export_interval: Option<Duration>,
+    pub auto_exported_at: Instant,
+    /// When the session started, naming the files exports are written to.
+    pub session_timestamp: String,
+    /// Where entries of the message log are also written, with `--debug-log`.
+    pub debug_log: Option<LineWriter<File>>,
+    /// Write each export to a new file named after the time of the export instead.
+    pub timestamped_exports: bool,
+    /// How bytes are written as hex in exports.
+    pub hex_format: HexFormat,
+    pub recorder: Option<csv::Writer<File>>,
+    pub recorder_flushed_at: Instant,
 }
 
+/// How often rows written while recording are flushed to disk.
+const RECORDER_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long quitting waits for the connected devices to disconnect.
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long quitting waits to unsubscribe from the characteristics subscribed to.
+const UNSUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long quitting waits for unsubscribing and disconnecting altogether.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// How often a snapshot is published to the status server.
+const STATUS_PUBLISH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long without input or updates before redraws slow down to the idle rate.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Returns a device as it is written out, with its addresses redacted with the `anonymize` key
+/// when there is one.
+fn exported<'a>(device: &'a DeviceInfo, anonymize: Option<&RandomState>) -> Cow<'a, DeviceInfo> {
+    match anonymize {
+        Some(key) => Cow::Owned(device.anonymized(key)),
+        None => Cow::Borrowed(device),
+    }
+}
+
+/// How many times between advertisements are kept per device for the interval estimate.
+const ADV_INTERVAL_HISTORY: usize = 8;
+
+/// How long a changed cell of the device table stays highlighted.
+const CHANGE_HIGHLIGHT: Duration = Duration::from_secs(1);
+
+/// How long the highlight must rest on a characteristic before it is read automatically, so
+/// scrolling through the list doesn't queue a read for each one passed.
+const AUTO_READ_DELAY: Duration = Duration::from_millis(300);
+
+/// How long between the readings the locate mode compares to tell hotter from colder.
+const LOCATE_TREND_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the device table flashes when a device matching the filters is found.
+const MATCH_FLASH: Duration = Duration::from_millis(300);
+
 impl App {
-    pub fn new() -> Self {
-        let (tx, rx) = mpsc::unbounded_channel();
-        Self {
+    pub fn new(args: &Args) -> Self {
+        let (tx, rx) = mpsc::channel(args.channel_capacity as usize);
+
+        let discovery_log = args.log_file.clone().map(|path| {
+            let (log_tx, log_rx) = mpsc::unbounded_channel();
+            let tx_clone = tx.clone();
+            let max_bytes = args.log_max_bytes;
+            tokio::task::spawn_blocking(move || discovery_log(path, max_bytes, log_rx, tx_clone));
+            log_tx
+        });
+
+        let status_tx = args.serve.map(|port| {
+            let (status_tx, status_rx) = watch::channel(StatusSnapshot::default());
+            let tx_clone = tx.clone();
+            tokio::spawn(async move { status_server(port, status_rx, tx_clone).await });
+            status_tx
+        });
+
+        let (keymap, keymap_error) = match load_keymap() {
+            Ok(keymap) => (keymap, None),
+            Err(e) => (Keymap::default(), Some(e)),
+        };
+        let (debug_log, debug_log_error) = match &args.debug_log {
+            Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => (Some(LineWriter::new(file)), None),
+                Err(e) => (None, Some(Error::DebugLog(e))),
+            },
+            None => (None, None),
+        };
+        let mut app = Self {
             tx,
             rx,
+            dropped_events: Arc::new(AtomicUsize::default()),
             loading_status: Arc::new(AtomicBool::default()),
-            pause_status: Arc::new(AtomicBool::default()),
+            pause_status: Arc::new(AtomicBool::new(args.start_paused)),
             table_state: TableState::default(),
             devices: Vec::new(),
             inspect_view: false,
             inspect_overlay_scroll: 0,
+            raw_properties_view: false,
+            raw_properties_scroll: 0,
+            payload_view: false,
+            payload_scroll: 0,
             selected_characteristics: Vec::new(),
+            property_filter: PropertyFilter::All,
+            characteristic_selected: 0,
+            char_values: HashMap::new(),
+            char_values_at: HashMap::new(),
+            read_cache_ttl: Duration::from_secs(args.read_cache_ttl),
+            auto_read: false,
+            auto_read_due: None,
+            write_without_response: HashSet::new(),
+            notify_stops: HashMap::new(),
             frame_count: 0,
+            spinner_ticked_at: Instant::now(),
             is_loading: false,
             error_view: false,
             error_message: String::new(),
+            input_mode: InputMode::Normal,
+            input_buffer: String::new(),
+            input_target: InputTarget::GoTo,
+            inspected_device: None,
+            last_connected: None,
+            connect_timeout: Duration::from_secs(args.connect_timeout),
+            confirm_quit: !args.no_confirm,
+            quit_confirm_view: false,
+            adapter_timeout: Duration::from_secs(args.adapter_timeout),
+            connect_started: None,
+            connect_attempt: 0,
+            nus_view: false,
+            nus_lines: Vec::new(),
+            nus_line_open: false,
+            nus_sender: None,
+            connections: HashMap::new(),
+            connections_view: false,
+            connections_selected: 0,
+            logs: Vec::new(),
+            notes: HashMap::new(),
+            favorites: load_favorites(),
+            columns: load_columns(),
+            keymap,
+            manufacturer_ascii: false,
+            short_uuids: true,
+            props_hex: false,
+            clipboard: None,
+            favorites_only: false,
+            name_filter: None,
+            name_filter_before: None,
+            name_filter_error: None,
+            alert_on_match: false,
+            alerted_ids: HashSet::new(),
+            bell_pending: false,
+            flash_at: None,
+            locate_id: None,
+            locate_beep: false,
+            locate_beeped_at: Instant::now(),
+            locate_reference: None,
+            locate_trend: None,
+            device_filters: Vec::new(),
+            scan_services: Vec::new(),
+            service_picker_view: false,
+            service_picker_services: Vec::new(),
+            service_picker_chosen: HashSet::new(),
+            service_picker_selected: 0,
+            sort_key: SortKey::Detected,
+            focus: Focus::Devices,
+            dense_layout: load_dense_layout(),
+            details_view: false,
+            detail_scroll: 0,
+            log_scroll: 0,
+            log_filter: String::new(),
+            max_devices: args.max_devices as usize,
+            eviction_policy: args.evict,
+            demo: args.demo,
+            anonymize_exports: args.anonymize,
+            anonymize_display: false,
+            anonymize_key: RandomState::new(),
+            last_seen: HashMap::new(),
+            adv_intervals: HashMap::new(),
+            changed_at: HashMap::new(),
+            evicted_devices: 0,
+            duplicate_discoveries: 0,
+            display_frozen: false,
+            frozen_devices: Vec::new(),
+            sort_ascending: true,
+            selected_ids: HashSet::new(),
+            path_loss_exponent: args.path_loss_exponent,
+            rssi_smoothing: args.rssi_smoothing,
+            reference_power: args.reference_power,
+            tick_rate: Duration::from_millis(args.tick_rate),
+            loading_tick_rate: Duration::from_millis(args.loading_tick_rate),
+            idle_tick_rate: Duration::from_millis(args.idle_tick_rate),
+            last_activity: Instant::now(),
+            dirty: true,
+            last_draw: Instant::now(),
+            discovery_log,
+            status_tx,
+            scan_task: None,
+            connect_task: None,
+            status_published_at: Instant::now(),
+            auto_export_interval: args
+                .auto_export_interval
+                .map(|minutes| Duration::from_secs(minutes * 60)),
+            auto_exported_at: Instant::now(),
+            session_timestamp: chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string(),
+            debug_log,
+            timestamped_exports: args.timestamped_exports,
+            hex_format: args.hex_format,
+            recorder: None,
+            recorder_flushed_at: Instant::now(),
+        };
+        if let Some(e) = keymap_error {
+            app.log(LogDirection::Error, e.to_string());
+        }
+        if let Some(e) = debug_log_error {
+            app.log(LogDirection::Error, e.to_string());
+        }
+        app
+    }
+
+    /// Returns how long the event loop waits for input before redrawing. While loading, it waits
+    /// until the spinner is due to advance, so the spinner keeps a steady pace however often
+    /// input arrives. Otherwise redraws slow down to the idle rate when nothing has changed for
+    /// a while.
+    pub fn poll_interval(&self) -> Duration {
+        if self.is_loading {
+            self.loading_tick_rate
+                .saturating_sub(self.spinner_ticked_at.elapsed())
+        } else if self.locate_id.is_some() || self.last_activity.elapsed() < IDLE_TIMEOUT {
+            self.tick_rate
+        } else {
+            self.idle_tick_rate
+        }
+    }
+
+    /// Returns whether the screen needs to be redrawn: when something changed, while the
+    /// spinner is animating, changed cells are highlighted or the device table flashes,
+    /// including the frame removing the highlight, or on a slow heartbeat for the timers and
+    /// counters shown.
+    pub fn needs_redraw(&self) -> bool {
+        self.dirty
+            || self.is_loading
+            || self
+                .flash_at
+                .is_some_and(|flash_at| self.last_draw < flash_at + MATCH_FLASH)
+            || self.last_draw.elapsed() >= self.idle_tick_rate
+            || self
+                .changed_at
+                .values()
+                .any(|changed_at| self.last_draw < *changed_at + CHANGE_HIGHLIGHT)
+    }
+
+    /// Advances the loading spinner once `loading_tick_rate` has passed since it last moved.
+    pub fn tick_spinner(&mut self) {
+        if self.spinner_ticked_at.elapsed() >= self.loading_tick_rate {
+            self.frame_count = self.frame_count.wrapping_add(1);
+            self.spinner_ticked_at = Instant::now();
         }
     }
 
+    /// Enters locate mode for the selected device, or leaves it.
+    pub fn toggle_locate(&mut self) {
+        self.locate_id = match self.locate_id {
+            Some(_) => None,
+            None => self.selected_device().map(|device| device.id.clone()),
+        };
+        self.locate_reference = None;
+        self.locate_trend = None;
+    }
+
+    /// Returns the RSSI of the device being located, smoothed when smoothing.
+    pub fn locate_rssi(&self) -> Option<f32> {
+        let id = self.locate_id.as_ref()?;
+        let device = self.devices.iter().find(|device| device.id == *id)?;
+        device
+            .smoothed_rssi
+            .or(device.properties.rssi.map(|rssi| rssi as f32))
+    }
+
+    /// Updates the trend of the located device, returning whether it is time to beep. The beeps
+    /// come faster as the signal grows stronger, from every 1.5 s down to every 100 ms.
+    pub fn locate_tick(&mut self) -> bool {
+        let Some(rssi) = self.locate_rssi() else {
+            return false;
+        };
+        if self
+            .locate_reference
+            .is_none_or(|(taken_at, _)| taken_at.elapsed() >= LOCATE_TREND_INTERVAL)
+        {
+            self.locate_trend = self.locate_reference.map(|(_, reference)| rssi - reference);
+            self.locate_reference = Some((Instant::now(), rssi));
+        }
+        let interval = Duration::from_millis(1500 - 14 * signal_percent(rssi) as u64);
+        if self.locate_beep && self.locate_beeped_at.elapsed() >= interval {
+            self.locate_beeped_at = Instant::now();
+            return true;
+        }
+        false
+    }
+
+    /// Returns whether the device table is flashing for a newly found match.
+    pub fn flashing(&self) -> bool {
+        self.flash_at
+            .is_some_and(|flash_at| flash_at.elapsed() < MATCH_FLASH)
+    }
+
+    /// Returns whether a cell of the device table changed recently enough to be highlighted.
+    pub fn recently_changed(&self, device: &DeviceInfo, column: Column) -> bool {
+        self.changed_at
+            .get(&(device.id.clone(), column))
+            .is_some_and(|changed_at| changed_at.elapsed() < CHANGE_HIGHLIGHT)
+    }
+
     pub async fn scan(&mut self) {
         let pause_signal_clone = Arc::clone(&self.pause_status);
         let tx_clone = self.tx.clone();
-        tokio::spawn(async move { bluetooth_scan(tx_clone, pause_signal_clone).await });
+        let dropped_events_clone = Arc::clone(&self.dropped_events);
+        let adapter_timeout = self.adapter_timeout;
+        let services = self.scan_services.clone();
+        self.scan_task = Some(if self.demo {
+            tokio::spawn(async move {
+                synthetic_scan(tx_clone, pause_signal_clone, dropped_events_clone).await
+            })
+        } else {
+            tokio::spawn(async move {
+                bluetooth_scan(
+                    tx_clone,
+                    pause_signal_clone,
+                    dropped_events_clone,
+                    adapter_timeout,
+                    services,
+                )
+                .await
+            })
+        });
+    }
+
+    /// Starts the scan task again once it has stopped, e.g. after the adapter went away, so the
+    /// adapter is acquired anew.
+    pub async fn restart_scan(&mut self) {
+        if self
+            .scan_task
+            .as_ref()
+            .is_some_and(|scan_task| !scan_task.is_finished())
+        {
+            self.error_message = "Scanning is still running.".to_owned();
+            self.error_view = true;
+            return;
+        }
+        self.scan().await;
+        self.log(LogDirection::Info, "Scan task restarted".to_owned());
+    }
+
+    /// Opens the service picker with the services currently scanned for checked.
+    pub fn open_service_picker(&mut self) {
+        let mut known: Vec<u16> = GATT_SERVICES.keys().copied().collect();
+        known.sort();
+        self.service_picker_services = self
+            .scan_services
+            .iter()
+            .filter(|uuid| service_name(uuid).is_none())
+            .copied()
+            .chain(known.into_iter().map(|short| bluetooth_uuid(short as u32)))
+            .collect();
+        self.service_picker_chosen = self.scan_services.iter().copied().collect();
+        self.service_picker_selected = 0;
+        self.service_picker_view = true;
+    }
+
+    /// Moves the highlight in the service picker by `offset` rows, wrapping around.
+    pub fn move_service_picker_selection(&mut self, offset: isize) {
+        let count = self.service_picker_services.len() as isize;
+        if count > 0 {
+            self.service_picker_selected =
+                (self.service_picker_selected as isize + offset).rem_euclid(count) as usize;
+        }
+    }
+
+    /// Checks or unchecks the highlighted service of the service picker.
+    pub fn toggle_picked_service(&mut self) {
+        if let Some(uuid) = self
+            .service_picker_services
+            .get(self.service_picker_selected)
+        {
+            if !self.service_picker_chosen.remove(uuid) {
+                self.service_picker_chosen.insert(*uuid);
+            }
+        }
+    }
+
+    /// Adds the typed UUID to the top of the service picker, checked.
+    pub fn add_picked_service(&mut self) {
+        let input = std::mem::take(&mut self.input_buffer);
+        self.stop_editing();
+        match normalize_uuid(&input) {
+            Ok(uuid) => {
+                if !self.service_picker_services.contains(&uuid) {
+                    self.service_picker_services.insert(0, uuid);
+                }
+                self.service_picker_chosen.insert(uuid);
+                self.service_picker_selected = self
+                    .service_picker_services
+                    .iter()
+                    .position(|service| *service == uuid)
+                    .unwrap_or_default();
+            }
+            Err(e) => {
+                self.error_message = e;
+                self.error_view = true;
+            }
+        }
+    }
+
+    /// Scans for the services checked in the service picker, restarting the scan with them.
+    pub async fn apply_service_picker(&mut self) {
+        self.service_picker_view = false;
+        self.scan_services = self
+            .service_picker_services
+            .iter()
+            .filter(|uuid| self.service_picker_chosen.contains(uuid))
+            .copied()
+            .collect();
+        if let Some(scan_task) = self.scan_task.take() {
+            scan_task.abort();
+        }
+        self.scan().await;
+        let message = if self.scan_services.is_empty() {
+            "Scanning for all devices".to_owned()
+        } else {
+            format!(
+                "Scanning for devices advertising {}",
+                self.scan_service_labels().join(", ")
+            )
+        };
+        self.log(LogDirection::Info, message);
+    }
+
+    /// Returns the names of the services scanned for, or the short UUIDs of custom ones.
+    pub fn scan_service_labels(&self) -> Vec<String> {
+        self.scan_services
+            .iter()
+            .map(|uuid| service_name(uuid).map_or_else(|| short_uuid(uuid), str::to_owned))
+            .collect()
+    }
+
+    /// Returns the indices into `devices` of the rows shown in the device table, in display
+    /// order. Rows are sorted by `sort_key` with favorites pinned to the top, and only
+    /// favorites are kept when `favorites_only` is set, and only devices passing every filter
+    /// in `device_filters` and whose name matches `name_filter`. While the display is frozen, the rows
+    /// are those of `frozen_devices`.
+    pub fn visible_indices(&self) -> Vec<usize> {
+        if self.display_frozen {
+            let indices: HashMap<&str, usize> = self
+                .devices
+                .iter()
+                .enumerate()
+                .map(|(i, device)| (device.id.as_str(), i))
+                .collect();
+            return self
+                .frozen_devices
+                .iter()
+                .filter_map(|device| indices.get(device.id.as_str()).copied())
+                .collect();
+        }
+        let mut indices: Vec<usize> = (0..self.devices.len())
+            .filter(|&i| self.matches_filters(&self.devices[i]))
+            .collect();
+        if self.sort_key == SortKey::Detected && !self.sort_ascending {
+            indices.reverse();
+        }
+        indices.sort_by(|&a, &b| {
+            self.sort_key
+                .compare(&self.devices[a], &self.devices[b], self.sort_ascending)
+        });
+        indices.sort_by_key(|&i| !self.favorites.contains(&self.devices[i].id));
+        indices
+    }
+
+    /// Returns the devices shown in the device table, in display order.
+    pub fn visible_devices(&self) -> Vec<&DeviceInfo> {
+        self.visible_indices()
+            .into_iter()
+            .map(|i| &self.devices[i])
+            .collect()
+    }
+
+    /// Returns the index into `devices` of the device at the selected row.
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.table_state.selected().unwrap_or(0);
+        self.visible_indices().get(selected).copied()
+    }
+
+    /// Returns the device at the selected row of the device table.
+    pub fn selected_device(&self) -> Option<&DeviceInfo> {
+        self.selected_index().map(|i| &self.devices[i])
+    }
+
+    /// Keeps the selection within the rows of the device table.
+    pub fn clamp_selection(&mut self) {
+        let count = self.visible_indices().len();
+        let selected = self.table_state.selected().unwrap_or(0);
+        self.table_state
+            .select(Some(selected.min(count.saturating_sub(1))));
+    }
+
+    /// Adds the selected device to the favorites, or removes it, and saves the favorites.
+    pub fn toggle_favorite(&mut self) {
+        if let Some(device) = self.selected_device() {
+            let id = device.id.clone();
+            if !self.favorites.remove(&id) {
+                self.favorites.insert(id.clone());
+            }
+            // Keep the same device selected now that it moved in or out of the pinned rows.
+            self.select_device_id(&id);
+            if let Err(e) = save_favorites(&self.favorites) {
+                self.log(
+                    LogDirection::Error,
+                    format!("Unable to save favorites: {}", e),
+                );
+            }
+        }
+    }
+
+    /// Switches between the normal and the dense layout, saving the choice for the next run.
+    /// The device table keeps the focus, since the panels after it are hidden when dense.
+    pub fn toggle_dense_layout(&mut self) {
+        self.dense_layout = !self.dense_layout;
+        self.details_view = false;
+        self.focus = Focus::Devices;
+        if let Err(e) = save_dense_layout(self.dense_layout) {
+            self.log(LogDirection::Error, format!("Unable to save layout: {}", e));
+        }
+    }
+
+    /// Selects the row of the device with the given id, e.g. after the rows were reordered.
+    fn select_device_id(&mut self, id: &str) {
+        let visible = self.visible_indices();
+        match visible.iter().position(|&i| self.devices[i].id == id) {
+            Some(row) => self.table_state.select(Some(row)),
+            None => {
+                let selected = self.table_state.selected().unwrap_or(0);
+                self.table_state
+                    .select(Some(selected.min(visible.len().saturating_sub(1))));
+            }
+        }
+    }
+
+    /// Sorts the device table by the next column, keeping the selected device selected.
+    pub fn cycle_sort_key(&mut self) {
+        let id = self.selected_device().map(|device| device.id.clone());
+        self.sort_key = self.sort_key.next();
+        if let Some(id) = id {
+            self.select_device_id(&id);
+        }
+    }
+
+    /// Reverses the direction of the sort, keeping the selected device selected.
+    pub fn toggle_sort_direction(&mut self) {
+        let id = self.selected_device().map(|device| device.id.clone());
+        self.sort_ascending = !self.sort_ascending;
+        if let Some(id) = id {
+            self.select_device_id(&id);
+        }
+    }
+
+    /// Marks the device at the selected row for a bulk export, or unmarks it.
+    pub fn toggle_selected(&mut self) {
+        if let Some(id) = self.selected_device().map(|device| device.id.clone()) {
+            if !self.selected_ids.remove(&id) {
+                self.selected_ids.insert(id);
+            }
+        }
+    }
+
+    /// Marks every device shown in the device table.
+    pub fn select_all(&mut self) {
+        let ids: Vec<String> = self
+            .visible_devices()
+            .iter()
+            .map(|device| device.id.clone())
+            .collect();
+        self.selected_ids.extend(ids);
+    }
+
+    /// Freezes the device table as it is shown, while devices keep being scanned and updated,
+    /// or applies the accumulated updates again.
+    pub fn toggle_display_frozen(&mut self) {
+        let id = self.selected_device().map(|device| device.id.clone());
+        if self.display_frozen {
+            self.display_frozen = false;
+            self.frozen_devices.clear();
+            // Devices aren't evicted while frozen, so the frozen rows stay valid
+            while self.devices.len() > self.max_devices {
+                let count = self.devices.len();
+                self.evict_device();
+                if self.devices.len() == count {
+                    break;
+                }
+            }
+        } else {
+            self.frozen_devices = self.visible_devices().into_iter().cloned().collect();
+            self.display_frozen = true;
+        }
+        if let Some(id) = id {
+            self.select_device_id(&id);
+        }
+    }
+
+    /// Scrolls the message log back towards older entries, or forward with a negative `delta`.
+    pub fn scroll_log(&mut self, delta: isize) {
+        self.log_scroll = self
+            .log_scroll
+            .saturating_add_signed(delta)
+            .min(self.visible_logs().len().saturating_sub(1));
+    }
+
+    /// Returns whether a log entry passes `log_filter`.
+    fn matches_log_filter(&self, entry: &LogEntry) -> bool {
+        self.log_filter.is_empty()
+            || entry
+                .message
+                .to_lowercase()
+                .contains(&self.log_filter.to_lowercase())
+    }
+
+    /// Returns the entries of the message log shown, oldest first: those passing `log_filter`.
+    pub fn visible_logs(&self) -> Vec<&LogEntry> {
+        self.logs
+            .iter()
+            .filter(|entry| self.matches_log_filter(entry))
+            .collect()
+    }
+
+    /// Starts editing the log filter, beginning from the current one.
+    pub fn edit_log_filter(&mut self) {
+        let log_filter = self.log_filter.clone();
+        self.start_editing(InputTarget::LogFilter);
+        self.input_buffer = log_filter;
+    }
+
+    /// Clears the log filter and stops editing it.
+    pub fn clear_log_filter(&mut self) {
+        self.log_filter.clear();
+        self.log_scroll = 0;
+        self.stop_editing();
+    }
+
+    /// Shows only the favorite devices, or all devices again.
+    pub fn toggle_favorites_only(&mut self) {
+        self.favorites_only = !self.favorites_only;
+        self.clamp_selection();
+    }
+
+    /// Adds a filter to the device table, or removes it when already active.
+    pub fn toggle_device_filter(&mut self, filter: DeviceFilter) {
+        match self.device_filters.iter().position(|f| *f == filter) {
+            Some(i) => {
+                self.device_filters.remove(i);
+            }
+            None => self.device_filters.push(filter),
+        }
+        self.clamp_selection();
+    }
+
+    /// Returns whether a device passes the filters applied to the device table.
+    fn matches_filters(&self, device: &DeviceInfo) -> bool {
+        (!self.favorites_only || self.favorites.contains(&device.id))
+            && self
+                .device_filters
+                .iter()
+                .all(|filter| filter.matches(device))
+            && self
+                .name_filter
+                .as_ref()
+                .is_none_or(|name_filter| name_filter.is_match(&device.name))
+    }
+
+    /// Returns the labels of the filters applied to the device table, for the info bar.
+    pub fn active_filters(&self) -> Vec<&'static str> {
+        let favorites = self.favorites_only.then_some("favorites");
+        let name = self.name_filter.is_some().then_some("name");
+        favorites
+            .into_iter()
+            .chain(self.device_filters.iter().map(DeviceFilter::label))
+            .chain(name)
+            .collect()
+    }
+
+    /// Starts editing the name filter, beginning from the pattern currently applied.
+    pub fn edit_name_filter(&mut self) {
+        self.name_filter_before = self.name_filter.clone();
+        self.name_filter_error = None;
+        self.start_editing(InputTarget::NameFilter);
+        if let Some(name_filter) = &self.name_filter {
+            self.input_buffer = name_filter.as_str().to_owned();
+        }
+    }
+
+    /// Applies the typed pattern as the name filter as soon as it compiles, so the table
+    /// follows the typing. An empty pattern removes the filter, and while the pattern doesn't
+    /// compile the last one that did stays applied.
+    fn update_name_filter(&mut self) {
+        if self.input_buffer.is_empty() {
+            self.name_filter = None;
+            self.name_filter_error = None;
+        } else {
+            match Regex::new(&self.input_buffer) {
+                Ok(name_filter) => {
+                    self.name_filter = Some(name_filter);
+                    self.name_filter_error = None;
+                }
+                Err(e) => {
+                    // The first line names the problem; the rest point at it in the pattern
+                    let message = e.to_string();
+                    self.name_filter_error = Some(
+                        message
+                            .lines()
+                            .last()
+                            .unwrap_or_default()
+                            .trim_start_matches("error: ")
+                            .to_owned(),
+                    );
+                }
+            }
+        }
+        self.clamp_selection();
+    }
+
+    /// Keeps the name filter last applied and stops editing it.
+    pub fn save_name_filter(&mut self) {
+        self.name_filter_error = None;
+        self.stop_editing();
+    }
+
+    /// Restores the name filter applied before editing it and stops editing.
+    pub fn cancel_name_filter(&mut self) {
+        self.name_filter = self.name_filter_before.take();
+        self.name_filter_error = None;
+        self.stop_editing();
+        self.clamp_selection();
+    }
+
+    /// Pauses scanning, or resumes it. Only the scan stops: the devices found, the sort, the
+    /// filters and the selection are kept as they are.
+    pub fn toggle_pause(&mut self) {
+        let paused = self.pause_status.load(Ordering::SeqCst);
+        self.pause_status.store(!paused, Ordering::SeqCst);
     }
 
     pub async fn connect(&mut self) {
-        let selected_device = self
-            .devices
-            .get(self.table_state.selected().unwrap_or(0))
-            .unwrap();
+        if let Some(device) = self.selected_device() {
+            self.connect_device(device.clone());
+        }
+    }
 
+    /// Connects to a device and inspects its characteristics once connected.
+    fn connect_device(&mut self, device: DeviceInfo) {
+        self.is_loading = true;
         self.pause_status.store(true, Ordering::SeqCst);
+        self.clear_characteristic_state();
+        self.inspected_device = Some(device.clone());
+
+        let device = Arc::new(device);
+        let tx_clone = self.tx.clone();
+        let timeout = self.connect_timeout;
+        self.connect_attempt += 1;
+        let attempt = self.connect_attempt;
+        self.connect_started = Some(Instant::now());
+        if let Some(connect_task) = self.connect_task.take() {
+            connect_task.abort();
+        }
+        self.connect_task = Some(tokio::spawn(async move {
+            get_characteristics(tx_clone, device, timeout, attempt).await
+        }));
+    }
+
+    /// Cancels the connection attempt in progress, dropping it wherever it is, and resumes
+    /// scanning. A device that wasn't connected before is disconnected, in case the platform
+    /// completes the connection anyway.
+    pub fn cancel_connect(&mut self) {
+        if let Some(connect_task) = self.connect_task.take() {
+            connect_task.abort();
+        }
+        // Anything the attempt sent before it was dropped is ignored
+        self.connect_attempt += 1;
+        self.is_loading = false;
+        self.connect_started = None;
+        self.pause_status.store(false, Ordering::SeqCst);
+        if let Some(device) = self.inspected_device.take() {
+            self.log(
+                LogDirection::Info,
+                format!("Cancelled connecting to {}", device.get_id()),
+            );
+            if !self.connections.contains_key(&device.id) {
+                tokio::spawn(async move { abandon_connection(Arc::new(device)).await });
+            }
+        }
+    }
+
+    /// Selects the last device connected to, wherever it moved in the table, and connects to
+    /// it again. Devices no longer shown are connected to anyway, for backends that can
+    /// connect to a device out of sight.
+    pub fn reconnect_last(&mut self) {
+        let Some(last) = self.last_connected.clone() else {
+            self.error_message = "No device was connected yet".to_string();
+            self.error_view = true;
+            return;
+        };
+        let visible = self
+            .visible_devices()
+            .iter()
+            .any(|device| device.id == last.id);
+        if visible {
+            self.select_device_id(&last.id);
+        } else {
+            self.error_message = "Device not currently visible".to_string();
+            self.error_view = true;
+        }
+        // Prefer the latest advertisement of the device over the one from the last connect
+        let device = self
+            .devices
+            .iter()
+            .find(|device| device.id == last.id)
+            .cloned()
+            .unwrap_or(last);
+        self.connect_device(device);
+    }
+
+    /// Stores a newly discovered device, or updates a known device in place, appending it to
+    /// the discovery log when enabled. The least recently seen device is evicted once there
+    /// are more than `max_devices`. The selection isn't kept on the same device, which
+    /// `on_device_updates` does once for a whole batch.
+    pub fn add_device(&mut self, mut device: DeviceInfo) {
+        self.dirty = true;
+        if let Some(discovery_log) = &self.discovery_log {
+            let device = exported(&device, self.export_key());
+            if discovery_log
+                .send(AdvertisementCsv::new(&device, self.hex_format))
+                .is_err()
+            {
+                self.discovery_log = None;
+            }
+        }
+        if let Err(e) = self.record(&device) {
+            self.recorder = None;
+            self.log(LogDirection::Error, format!("Recording stopped: {}", e));
+        }
+        let now = Instant::now();
+        if let Some(last_seen) = self.last_seen.insert(device.id.clone(), now) {
+            let intervals = self.adv_intervals.entry(device.id.clone()).or_default();
+            if intervals.len() == ADV_INTERVAL_HISTORY {
+                intervals.pop_front();
+            }
+            intervals.push_back(now - last_seen);
+        }
+        let known = self.devices.iter().position(|d| d.id == device.id);
+        if let Some(factor) = self.rssi_smoothing {
+            device.smoothed_rssi = match (
+                known.and_then(|i| self.devices[i].smoothed_rssi),
+                device.properties.rssi,
+            ) {
+                (Some(smoothed), Some(rssi)) => {
+                    Some(factor * rssi as f32 + (1.0 - factor) * smoothed)
+                }
+                (None, Some(rssi)) => Some(rssi as f32),
+                // An advertisement without an RSSI keeps the average
+                (smoothed, None) => smoothed,
+            };
+        }
+        match known.map(|i| &mut self.devices[i]) {
+            Some(known) => {
+                device.detected_at = std::mem::take(&mut known.detected_at);
+                device.times_seen = known.times_seen.saturating_add(1);
+                self.duplicate_discoveries += 1;
+                // Devices often send their name in the scan response only, so an advertisement
+                // without one keeps the name already known
+                if device.properties.local_name.is_none() {
+                    device.name = std::mem::take(&mut known.name);
+                }
+                self.changed_at
+                    .retain(|_, changed_at| changed_at.elapsed() < CHANGE_HIGHLIGHT);
+                for column in self.columns.iter().filter(|c| c.changed(known, &device)) {
+                    self.changed_at.insert((device.id.clone(), *column), now);
+                }
+                *known = device;
+            }
+            None => {
+                if self.alert_on_match
+                    && !self.active_filters().is_empty()
+                    && self.matches_filters(&device)
+                    && self.alerted_ids.insert(device.id.clone())
+                {
+                    self.bell_pending = true;
+                    self.flash_at = Some(now);
+                }
+                self.devices.push(device);
+                if self.devices.len() > self.max_devices && !self.display_frozen {
+                    self.evict_device();
+                }
+            }
+        }
+    }
+
+    /// Removes the least recently seen device, or the one with the weakest signal, as chosen
+    /// by `eviction_policy`. Favorites and connected devices are exempt. A device just added
+    /// can be the one evicted, when its signal is the weakest.
+    fn evict_device(&mut self) {
+        let candidates = self.devices.iter().enumerate().filter(|(_, d)| {
+            !self.favorites.contains(&d.id) && !self.connections.contains_key(&d.id)
+        });
+        let evicted = match self.eviction_policy {
+            EvictionPolicy::Oldest => candidates
+                .min_by_key(|(_, d)| self.last_seen.get(&d.id))
+                .map(|(i, _)| i),
+            // Devices without an RSSI sort first, as `None` is less than any value
+            EvictionPolicy::Weakest => candidates
+                .min_by_key(|(_, d)| d.properties.rssi)
+                .map(|(i, _)| i),
+        };
+        if let Some(i) = evicted {
+            let device = self.devices.remove(i);
+            self.last_seen.remove(&device.id);
+            self.adv_intervals.remove(&device.id);
+            self.changed_at.retain(|(id, _), _| *id != device.id);
+            self.selected_ids.remove(&device.id);
+            self.evicted_devices += 1;
+        }
+    }
+
+    /// Starts recording every advertisement to a new CSV file, or stops the current recording.
+    pub fn toggle_recording(&mut self) -> Result<String, Error> {
+        match self.recorder.take() {
+            Some(mut recorder) => {
+                recorder.flush()?;
+                let message = "Recording stopped.".to_string();
+                self.log(LogDirection::Info, message.clone());
+                Ok(message)
+            }
+            None => {
+                let now = chrono::Local::now();
+                let timestamp = now.format("%Y-%m-%d_%H-%M-%S").to_string();
+                let file_path = format!("btlescan_record_{}.csv", timestamp);
+                let file = File::create(&file_path)?;
+                self.recorder = Some(csv::Writer::from_writer(file));
+                self.recorder_flushed_at = Instant::now();
+                let message = format!("Recording advertisements to {}.", file_path);
+                self.log(LogDirection::Info, message.clone());
+                Ok(message)
+            }
+        }
+    }
+
+    /// Publishes the devices and the scanner state to the status server, when serving, at most
+    /// every `STATUS_PUBLISH_INTERVAL`. Addresses are redacted with `--anonymize`.
+    pub fn publish_status(&mut self) {
+        let Some(status_tx) = &self.status_tx else {
+            return;
+        };
+        if self.status_published_at.elapsed() < STATUS_PUBLISH_INTERVAL {
+            return;
+        }
+        let snapshot = StatusSnapshot {
+            status: StatusJson {
+                scanning: !self.pause_status.load(Ordering::SeqCst),
+                recording: self.recorder.is_some(),
+                devices: self.devices.len(),
+                connections: self.connections.len(),
+                dropped_events: self.dropped_events.load(Ordering::SeqCst),
+                evicted_devices: self.evicted_devices,
+                duplicate_discoveries: self.duplicate_discoveries,
+            },
+            devices: self
+                .devices
+                .iter()
+                .map(|device| {
+                    DeviceJson::new(&exported(device, self.export_key()), self.hex_format)
+                })
+                .collect(),
+        };
+        let _ = status_tx.send(snapshot);
+        self.status_published_at = Instant::now();
+    }
+
+    /// Appends an advertisement to the recording, if one is in progress.
+    fn record(&mut self, device: &DeviceInfo) -> Result<(), Error> {
+        let key = self.anonymize_exports.then_some(&self.anonymize_key);
+        let hex_format = self.hex_format;
+        if let Some(recorder) = &mut self.recorder {
+            let device = exported(device, key);
+            recorder.serialize(AdvertisementCsv::new(&device, hex_format))?;
+            if self.recorder_flushed_at.elapsed() >= RECORDER_FLUSH_INTERVAL {
+                recorder.flush()?;
+                self.recorder_flushed_at = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    /// Reacts to a batch of updates. A sorted table can move the selected device, or place new
+    /// devices above it, so the device is selected again once the batch is handled rather
+    /// than after every advertisement.
+    pub fn on_device_updates(&mut self, updates: Vec<DeviceData>) {
+        let moved = updates
+            .iter()
+            .any(|data| matches!(data, DeviceData::DeviceInfo(_)));
+        let id = if moved && self.table_state.selected().is_some() {
+            self.selected_device().map(|device| device.id.clone())
+        } else {
+            None
+        };
+        for data in updates {
+            self.on_device_data(data);
+        }
+        if let Some(id) = id {
+            self.select_device_id(&id);
+        }
+    }
+
+    /// Reacts to an update sent by the scanner or by a task working on a device.
+    pub fn on_device_data(&mut self, data: DeviceData) {
+        match data {
+            DeviceData::DeviceInfo(device) => self.add_device(*device),
+            // Left over from a connection attempt that was cancelled since
+            DeviceData::Characteristics(attempt, _) if attempt != self.connect_attempt => {}
+            DeviceData::Characteristics(_, characteristics) => {
+                self.on_connected(characteristics.len());
+                self.selected_characteristics = characteristics;
+                self.read_device_information();
+                self.inspect_view = true;
+                self.is_loading = false;
+                self.connect_started = None;
+                self.connect_task = None;
+            }
+            DeviceData::NusData(data) => self.push_nus_data(&data),
+            DeviceData::CharacteristicValue(uuid, value) => {
+                self.on_characteristic_value(uuid, value)
+            }
+            DeviceData::CharacteristicWritten(uuid, value) => {
+                self.on_characteristic_written(uuid, value)
+            }
+            DeviceData::WriteVerified(uuid, written, read) => {
+                self.on_write_verified(uuid, written, read)
+            }
+            DeviceData::Disconnected(id) => self.on_disconnected(&id),
+            DeviceData::Status(message) => self.log(LogDirection::Info, message),
+            // Logged without interrupting, as nobody may be watching
+            DeviceData::Checkpoint(Ok(message)) => self.log(LogDirection::Info, message),
+            DeviceData::Checkpoint(Err(e)) => {
+                self.log(LogDirection::Error, format!("Checkpoint failed: {}", e))
+            }
+            DeviceData::ConnectError(attempt, error) if attempt != self.connect_attempt => {
+                self.log(LogDirection::Error, error.to_string());
+            }
+            DeviceData::ConnectError(_, error) => {
+                self.show_error(error);
+                self.is_loading = false;
+                self.connect_started = None;
+                self.connect_task = None;
+            }
+            // Unrelated to the connection attempt in progress, if any, which carries on
+            DeviceData::Error(error) => self.show_error(error),
+        }
+    }
+
+    /// Logs an error and shows it in the error popup.
+    fn show_error(&mut self, error: Error) {
+        self.log(LogDirection::Error, error.to_string());
+        self.error_message = error.to_string();
+        self.error_view = true;
+    }
+
+    /// Records that the inspected device is connected and watches it for a disconnect.
+    /// `characteristics` is the number of characteristics discovered on it.
+    pub fn on_connected(&mut self, characteristics: usize) {
+        self.last_connected = self.inspected_device.clone();
+        if let Some(device) = &self.inspected_device {
+            if let Some(connection) = self.connections.get_mut(&device.id) {
+                connection.characteristics = characteristics;
+                return;
+            }
+            self.connections.insert(
+                device.id.clone(),
+                ConnectionState {
+                    device: device.clone(),
+                    connected_at: Instant::now(),
+                    mtu: None,
+                    interval: None,
+                    latency: None,
+                    supervision_timeout: None,
+                    characteristics,
+                    subscriptions: HashSet::new(),
+                },
+            );
+            let message = format!("Connected to {}", device.get_id());
+
+            let device = Arc::new(device.clone());
+            let tx_clone = self.tx.clone();
+            tokio::spawn(async move { watch_connection(tx_clone, device).await });
+
+            self.log(LogDirection::Info, message);
+        }
+    }
+
+    /// Returns the open connections, oldest first.
+    pub fn connection_list(&self) -> Vec<&ConnectionState> {
+        let mut connections: Vec<&ConnectionState> = self.connections.values().collect();
+        connections.sort_by_key(|connection| connection.connected_at);
+        connections
+    }
+
+    /// Returns whether to quit right away. With devices connected, the quit confirmation is
+    /// shown instead, unless started with `--no-confirm`.
+    pub fn request_quit(&mut self) -> bool {
+        if self.connections.is_empty() || !self.confirm_quit {
+            return true;
+        }
+        self.quit_confirm_view = true;
+        false
+    }
+
+    /// Disconnects every connected device and waits for it, giving up after
+    /// `DISCONNECT_TIMEOUT` so a stuck device can't keep the application from quitting.
+    pub async fn disconnect_all(&self) {
+        let disconnects = self.connections.values().map(|connection| {
+            let device = Arc::new(connection.device.clone());
+            disconnect(self.tx.clone(), device)
+        });
+        let _ =
+            tokio::time::timeout(DISCONNECT_TIMEOUT, futures::future::join_all(disconnects)).await;
+    }
+
+    /// Unsubscribes from the characteristics of the inspected device, including the terminal's,
+    /// then disconnects every connected device, so no device is left notifying a closed
+    /// connection. Each step has its own timeout, and `SHUTDOWN_TIMEOUT` bounds them all.
+    pub async fn shutdown(&mut self) {
+        let mut uuids: Vec<Uuid> = self.notify_stops.keys().copied().collect();
+        if self.nus_sender.is_some() {
+            uuids.push(NUS_TX_UUID);
+        }
+        let unsubscribes: Vec<_> = match &self.inspected_device {
+            Some(device) => {
+                let device = Arc::new(device.clone());
+                uuids
+                    .into_iter()
+                    .map(|uuid| unsubscribe(self.tx.clone(), Arc::clone(&device), uuid))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        let shutdown = async {
+            let _ =
+                tokio::time::timeout(UNSUBSCRIBE_TIMEOUT, futures::future::join_all(unsubscribes))
+                    .await;
+            self.disconnect_all().await;
+        };
+        let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown).await;
+    }
+
+    /// Disconnects the connection selected in the connections panel.
+    pub fn disconnect_selected(&mut self) {
+        if let Some(connection) = self.connection_list().get(self.connections_selected) {
+            let device = Arc::new(connection.device.clone());
+            let tx_clone = self.tx.clone();
+            tokio::spawn(async move { disconnect(tx_clone, device).await });
+        }
+    }
+
+    /// Records that a device disconnected, logging how long the connection was held.
+    /// The inspect view and terminal of the device are closed since they need the connection.
+    pub fn on_disconnected(&mut self, id: &str) {
+        if let Some(connection) = self.connections.remove(id) {
+            self.log(
+                LogDirection::Info,
+                format!(
+                    "Disconnected from {} after {}",
+                    connection.device.get_id(),
+                    format_duration(connection.connected_at.elapsed())
+                ),
+            );
+        }
+        if self.inspected_device.as_ref().is_some_and(|d| d.id == id) {
+            if self.nus_view {
+                self.close_nus_terminal();
+            }
+            self.inspect_view = false;
+            self.selected_characteristics.clear();
+            self.clear_characteristic_state();
+            self.inspected_device = None;
+        }
+        self.connections_selected = self
+            .connections_selected
+            .min(self.connections.len().saturating_sub(1));
+    }
+
+    /// Appends an entry to the message log. A log scrolled back stays on the same entries.
+    /// With `--debug-log` the entry is written to the file too, ignoring write errors so a full
+    /// disk doesn't take the UI down.
+    pub fn log(&mut self, direction: LogDirection, message: String) {
+        let entry = LogEntry {
+            timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+            direction,
+            message,
+        };
+        if self.log_scroll > 0 && self.matches_log_filter(&entry) {
+            self.log_scroll += 1;
+        }
+        if let Some(debug_log) = &mut self.debug_log {
+            let _ = writeln!(
+                debug_log,
+                "{} {} {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                direction.label(),
+                entry.message
+            );
+        }
+        self.logs.push(entry);
+    }
+
+    /// Switches to `InputMode::Editing` with an empty input for the given target.
+    pub fn start_editing(&mut self, target: InputTarget) {
+        self.input_buffer.clear();
+        self.input_target = target;
+        self.input_mode = InputMode::Editing;
+    }
+
+    /// Returns to `InputMode::Normal`, discarding the input.
+    pub fn stop_editing(&mut self) {
+        self.input_buffer.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Starts editing the note of the selected device, beginning from its current note.
+    pub fn edit_note(&mut self) {
+        if let Some(device) = self.selected_device() {
+            let note = self.notes.get(&device.id).cloned().unwrap_or_default();
+            self.start_editing(InputTarget::Note);
+            self.input_buffer = note;
+        }
+    }
+
+    /// Saves the typed note for the selected device. An empty note removes it.
+    pub fn save_note(&mut self) {
+        if let Some(id) = self.selected_device().map(|device| device.id.clone()) {
+            let note = self.input_buffer.trim().to_string();
+            if note.is_empty() {
+                self.notes.remove(&id);
+            } else {
+                self.notes.insert(id, note);
+            }
+        }
+        self.stop_editing();
+    }
+
+    /// Reacts to the input changing while editing.
+    pub fn on_input_changed(&mut self) {
+        match self.input_target {
+            InputTarget::GoTo => self.go_to_device(),
+            InputTarget::NameFilter => self.update_name_filter(),
+            InputTarget::LogFilter => {
+                // The scroll counts back from the newest entry shown, which changes with the filter
+                self.log_filter = self.input_buffer.clone();
+                self.log_scroll = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Selects the first device whose identifier starts with the typed input, ignoring case.
+    /// Unlike a filter, the other devices stay in the table.
+    pub fn go_to_device(&mut self) {
+        let prefix = self.input_buffer.to_lowercase();
+        if let Some(index) = self
+            .visible_devices()
+            .iter()
+            .position(|device| device.get_id().to_lowercase().starts_with(&prefix))
+        {
+            self.table_state.select(Some(index));
+        }
+    }
+
+    /// Connects to the device with the typed address or UUID, even when it is not in the
+    /// device table.
+    pub async fn connect_by_address(&mut self) {
+        let address = std::mem::take(&mut self.input_buffer);
+        self.stop_editing();
+
+        match find_peripheral(&address).await {
+            Ok(device) => self.connect_device(device),
+            Err(e) => {
+                self.log(LogDirection::Error, e.to_string());
+                self.error_message = e.to_string();
+                self.error_view = true;
+            }
+        }
+    }
+
+    /// Estimates the distance to a device in meters, or `None` when its RSSI is unknown. The
+    /// smoothed RSSI is used when smoothing.
+    pub fn estimate_distance(&self, device: &DeviceInfo) -> Option<f32> {
+        let rssi = device.display_rssi().parse::<i16>().ok()?;
+        let tx_power = device
+            .tx_power
+            .parse::<i16>()
+            .unwrap_or(self.reference_power);
+        Some(estimate_distance(
+            rssi,
+            Some(tx_power),
+            self.path_loss_exponent,
+        ))
+    }
+
+    /// Estimates how often a device advertises, as the median of the recent times between its
+    /// advertisements. Returns `None` for devices seen only once.
+    pub fn advertising_interval(&self, device: &DeviceInfo) -> Option<Duration> {
+        let mut intervals: Vec<Duration> = self
+            .adv_intervals
+            .get(&device.id)?
+            .iter()
+            .copied()
+            .collect();
+        intervals.sort();
+        intervals.get(intervals.len() / 2).copied()
+    }
+
+    /// Returns the transmit power in dBm last read from the TX Power Level characteristic of a
+    /// device, when it is the inspected device.
+    pub fn gatt_tx_power(&self, device: &DeviceInfo) -> Option<i8> {
+        self.inspected_device
+            .as_ref()
+            .filter(|inspected| inspected.id == device.id)
+            .and_then(|_| self.char_values.get(&TX_POWER_LEVEL_UUID))
+            .and_then(|data| data.first())
+            .map(|&level| level as i8)
+    }
+
+    /// Returns the battery level of a device: the last value read from its Battery Level
+    /// characteristic when it is the inspected device, or else its advertised Battery Service
+    /// data. Other values are never treated as a level.
+    pub fn battery_level(&self, device: &DeviceInfo) -> Option<u8> {
+        let read = self
+            .inspected_device
+            .as_ref()
+            .filter(|inspected| inspected.id == device.id)
+            .and_then(|_| self.char_values.get(&BATTERY_LEVEL_UUID));
+        let advertised = || {
+            device
+                .service_data
+                .iter()
+                .find(|(uuid, _)| uuid_to_u16(uuid) == Some(0x180F))
+                .map(|(_, data)| data)
+        };
+        read.or_else(advertised)
+            .and_then(|data| data.first().copied())
+    }
+
+    /// Returns `true` if the inspected device exposes the Nordic UART Service RX and TX
+    /// characteristics.
+    pub fn has_nus(&self) -> bool {
+        let nus = |uuid| {
+            self.selected_characteristics
+                .iter()
+                .any(|c| c.service == NUS_SERVICE_UUID && c.uuid == uuid)
+        };
+        nus(NUS_RX_UUID) && nus(NUS_TX_UUID)
+    }
+
+    /// Returns the characteristics of the inspected device that pass the property filter.
+    /// They are grouped by service, in the order the inspect overlay lists them.
+    pub fn visible_characteristics(&self) -> Vec<&Characteristic> {
+        let mut characteristics: Vec<&Characteristic> = self
+            .selected_characteristics
+            .iter()
+            .filter(|c| self.property_filter.matches(c.properties))
+            .collect();
+        characteristics.sort_by_key(|c| c.service.to_string());
+        characteristics
+    }
+
+    /// Returns the characteristic highlighted in the inspect overlay.
+    pub fn selected_characteristic(&self) -> Option<&Characteristic> {
+        self.visible_characteristics()
+            .get(self.characteristic_selected)
+            .copied()
+    }
+
+    /// Moves the highlight in the inspect overlay by `offset` rows, wrapping around.
+    pub fn move_characteristic_selection(&mut self, offset: isize) {
+        let count = self.visible_characteristics().len() as isize;
+        if count > 0 {
+            self.characteristic_selected =
+                (self.characteristic_selected as isize + offset).rem_euclid(count) as usize;
+            self.schedule_auto_read();
+        }
+    }
+
+    /// Switches reading characteristics as they are highlighted on or off.
+    pub fn toggle_auto_read(&mut self) {
+        self.auto_read = !self.auto_read;
+        self.schedule_auto_read();
+    }
 
-        let device = Arc::new(selected_device.clone());
+    /// Schedules reading the highlighted characteristic when reading automatically, pushing
+    /// back a read already scheduled.
+    pub fn schedule_auto_read(&mut self) {
+        self.auto_read_due = self.auto_read.then(|| Instant::now() + AUTO_READ_DELAY);
+    }
+
+    /// Reads the highlighted characteristic once its automatic read is due. Characteristics
+    /// that aren't readable are skipped, and values still cached aren't read again.
+    pub fn auto_read_tick(&mut self) {
+        if !self
+            .auto_read_due
+            .is_some_and(|due| Instant::now() >= due && self.inspect_view)
+        {
+            return;
+        }
+        self.auto_read_due = None;
+        if self
+            .selected_characteristic()
+            .is_some_and(|c| c.properties.contains(CharPropFlags::READ))
+        {
+            self.read_selected_characteristic(false);
+        }
+    }
+
+    /// Reads the value of the highlighted characteristic. A value read within `read_cache_ttl`
+    /// is reused instead, unless `refresh` is set.
+    pub fn read_selected_characteristic(&mut self, refresh: bool) {
+        let Some(characteristic) = self.selected_characteristic() else {
+            return;
+        };
+        if !characteristic.properties.contains(CharPropFlags::READ) {
+            self.error_message = format!("{} is not readable", characteristic.uuid);
+            self.error_view = true;
+            return;
+        }
+        let cached = self
+            .char_values_at
+            .get(&characteristic.uuid)
+            .is_some_and(|read_at| read_at.elapsed() < self.read_cache_ttl);
+        if cached && !refresh {
+            return;
+        }
+        if let Some(device) = &self.inspected_device {
+            let uuid = characteristic.uuid;
+            let device = Arc::new(device.clone());
+            let tx_clone = self.tx.clone();
+            tokio::spawn(async move { read_characteristic(tx_clone, device, uuid).await });
+        }
+    }
+
+    /// Switches the highlighted characteristic between writing with and without response, when
+    /// it supports both.
+    pub fn toggle_write_type(&mut self) {
+        let Some(characteristic) = self.selected_characteristic() else {
+            return;
+        };
+        let uuid = characteristic.uuid;
+        if !characteristic.has_write_choice() {
+            self.error_message =
+                format!("{} can't be written both with and without response", uuid);
+            self.error_view = true;
+            return;
+        }
+        if !self.write_without_response.remove(&uuid) {
+            self.write_without_response.insert(uuid);
+        }
+    }
+
+    /// Reads the strings of the Device Information Service, when the inspected device has it,
+    /// so the inspect overlay can show the device's identity. Its TX Power Level is read as well,
+    /// to show next to the advertised TX power.
+    pub fn read_device_information(&mut self) {
+        let uuids: Vec<Uuid> = self
+            .selected_characteristics
+            .iter()
+            .filter(|c| {
+                (uuid_to_u16(&c.service) == Some(DEVICE_INFORMATION_UUID)
+                    && device_information_label(&c.uuid).is_some())
+                    || c.uuid == TX_POWER_LEVEL_UUID
+            })
+            .filter(|c| c.properties.contains(CharPropFlags::READ))
+            .map(|c| c.uuid)
+            .collect();
+        if uuids.is_empty() {
+            return;
+        }
+        if let Some(device) = &self.inspected_device {
+            let device = Arc::new(device.clone());
+            let tx_clone = self.tx.clone();
+            tokio::spawn(async move { read_characteristics(tx_clone, device, uuids).await });
+        }
+    }
+
+    /// Prompts for a value to write to the highlighted characteristic. With `verify` the value
+    /// is read back after the write, so the characteristic must be readable as well.
+    pub fn start_write(&mut self, verify: bool) {
+        let Some(characteristic) = self.selected_characteristic() else {
+            return;
+        };
+        if !PropertyFilter::Write.matches(characteristic.properties) {
+            self.error_message = format!("{} is not writable", characteristic.uuid);
+            self.error_view = true;
+            return;
+        }
+        if verify && !characteristic.properties.contains(CharPropFlags::READ) {
+            self.error_message = format!(
+                "{} is not readable, so a write can't be verified",
+                characteristic.uuid
+            );
+            self.error_view = true;
+            return;
+        }
+        self.start_editing(if verify {
+            InputTarget::WriteVerify
+        } else {
+            InputTarget::Write
+        });
+    }
+
+    /// Writes the typed hex bytes to the highlighted characteristic, reading them back afterwards
+    /// when writing with verification.
+    pub fn write_selected_characteristic(&mut self) {
+        let input = std::mem::take(&mut self.input_buffer);
+        let verify = self.input_target == InputTarget::WriteVerify;
+        self.stop_editing();
+        let value = match hex_to_bytes(&input) {
+            Ok(value) => value,
+            Err(e) => {
+                self.error_message = e;
+                self.error_view = true;
+                return;
+            }
+        };
+        if let (Some(characteristic), Some(device)) =
+            (self.selected_characteristic(), &self.inspected_device)
+        {
+            let uuid = characteristic.uuid;
+            let Some(write_type) =
+                characteristic.write_type(self.write_without_response.contains(&uuid))
+            else {
+                return;
+            };
+            let device = Arc::new(device.clone());
+            let tx_clone = self.tx.clone();
+            tokio::spawn(async move {
+                write_characteristic(tx_clone, device, uuid, value, write_type, verify).await
+            });
+        }
+    }
+
+    /// Subscribes to the highlighted characteristic, or unsubscribes when already subscribed.
+    pub fn toggle_notify_selected_characteristic(&mut self) {
+        let Some(characteristic) = self.selected_characteristic() else {
+            return;
+        };
+        let uuid = characteristic.uuid;
+        let subscribable = characteristic.subscription_label().is_some();
+        let Some(device) = self.inspected_device.clone() else {
+            return;
+        };
+
+        if self.notify_stops.remove(&uuid).is_some() {
+            if let Some(connection) = self.connections.get_mut(&device.id) {
+                connection.subscriptions.remove(&uuid);
+            }
+            return;
+        }
+        if !subscribable {
+            self.error_message = format!(
+                "Cannot subscribe to {}: it supports neither notify nor indicate",
+                uuid
+            );
+            self.error_view = true;
+            return;
+        }
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        self.notify_stops.insert(uuid, stop_tx);
+        if let Some(connection) = self.connections.get_mut(&device.id) {
+            connection.subscriptions.insert(uuid);
+        }
+        let device = Arc::new(device);
         let tx_clone = self.tx.clone();
+        tokio::spawn(
+            async move { subscribe_characteristic(tx_clone, device, uuid, stop_rx).await },
+        );
+    }
+
+    /// Forgets the values and write types of the inspected device's characteristics and ends its
+    /// subscriptions, before another device is inspected.
+    fn clear_characteristic_state(&mut self) {
+        if let Some(connection) = self
+            .inspected_device
+            .as_ref()
+            .and_then(|device| self.connections.get_mut(&device.id))
+        {
+            for uuid in self.notify_stops.keys() {
+                connection.subscriptions.remove(uuid);
+            }
+        }
+        self.notify_stops.clear();
+        self.char_values.clear();
+        self.char_values_at.clear();
+        self.write_without_response.clear();
+        self.characteristic_selected = 0;
+    }
+
+    /// Stores a value read from, or notified by, a characteristic.
+    pub fn on_characteristic_value(&mut self, uuid: Uuid, value: Vec<u8>) {
+        self.log(
+            LogDirection::Received,
+            format!("{}: {}", uuid, bytes_to_hex(&value)),
+        );
+        self.char_values.insert(uuid, value);
+        self.char_values_at.insert(uuid, Instant::now());
+    }
+
+    /// Compares a value read back after a write with the value written, logging PASS or FAIL.
+    /// A mismatch is also shown in the notification overlay.
+    pub fn on_write_verified(&mut self, uuid: Uuid, written: Vec<u8>, read: Vec<u8>) {
+        if written == read {
+            self.log(
+                LogDirection::Info,
+                format!("PASS {}: read back {}", uuid, bytes_to_hex(&read)),
+            );
+        } else {
+            let message = format!(
+                "FAIL {}: wrote {}, read back {}",
+                uuid,
+                bytes_to_hex(&written),
+                bytes_to_hex(&read)
+            );
+            self.log(LogDirection::Error, message.clone());
+            self.error_message = message;
+            self.error_view = true;
+        }
+        self.char_values.insert(uuid, read);
+        self.char_values_at.insert(uuid, Instant::now());
+    }
+
+    /// Records a value written to a characteristic.
+    pub fn on_characteristic_written(&mut self, uuid: Uuid, value: Vec<u8>) {
+        self.log(
+            LogDirection::Sent,
+            format!("{}: {}", uuid, bytes_to_hex(&value)),
+        );
+        // The value read before the write is likely stale, so the next read goes to the device
+        self.char_values_at.remove(&uuid);
+    }
+
+    /// Shows the characteristics with the next property, scrolling back to the top since the
+    /// filtered list can be shorter than the scroll position.
+    pub fn cycle_property_filter(&mut self) {
+        self.property_filter = self.property_filter.next();
+        self.inspect_overlay_scroll = 0;
+        self.characteristic_selected = 0;
+    }
+
+    /// Returns the Nordic UART Service TX characteristic of the inspected device.
+    pub fn nus_tx_characteristic(&self) -> Option<&Characteristic> {
+        self.selected_characteristics
+            .iter()
+            .find(|c| c.service == NUS_SERVICE_UUID && c.uuid == NUS_TX_UUID)
+    }
 
-        tokio::spawn(async move { get_characteristics(tx_clone, device).await });
+    /// Opens the Nordic UART Service console for the inspected device.
+    /// The TX characteristic must support notifications or indications to stream anything back.
+    pub fn open_nus_terminal(&mut self) {
+        if self
+            .nus_tx_characteristic()
+            .is_some_and(|c| c.subscription_label().is_none())
+        {
+            self.error_message = format!(
+                "Cannot subscribe to {}: it supports neither notify nor indicate",
+                NUS_TX_UUID
+            );
+            self.error_view = true;
+            return;
+        }
+        if let Some(device) = &self.inspected_device {
+            let (line_tx, line_rx) = mpsc::unbounded_channel();
+            if let Some(connection) = self.connections.get_mut(&device.id) {
+                connection.subscriptions.insert(NUS_TX_UUID);
+            }
+            let device = Arc::new(device.clone());
+            let tx_clone = self.tx.clone();
+            tokio::spawn(async move { nus_terminal(tx_clone, device, line_rx).await });
+
+            self.nus_sender = Some(line_tx);
+            self.nus_lines.clear();
+            self.nus_line_open = false;
+            self.start_editing(InputTarget::NusTerminal);
+            self.nus_view = true;
+        }
+    }
+
+    /// Closes the Nordic UART Service console, which unsubscribes from the device.
+    pub fn close_nus_terminal(&mut self) {
+        self.nus_sender = None;
+        self.stop_editing();
+        self.nus_view = false;
+        if let Some(device) = &self.inspected_device {
+            if let Some(connection) = self.connections.get_mut(&device.id) {
+                connection.subscriptions.remove(&NUS_TX_UUID);
+            }
+        }
     }
 
-    pub fn get_devices_csv(&self) -> Result<String, Box<dyn Error>> {
+    /// Writes the typed line to the Nordic UART Service RX characteristic.
+    pub fn send_nus_line(&mut self) {
+        let line = std::mem::take(&mut self.input_buffer);
+        if let Some(sender) = &self.nus_sender {
+            self.nus_lines.push(format!("> {}", line));
+            self.nus_line_open = false;
+            let _ = sender.send(line);
+        }
+    }
+
+    /// Appends data notified by the Nordic UART Service TX characteristic to the console.
+    /// Data without a trailing newline stays open so the next notification continues the line.
+    pub fn push_nus_data(&mut self, data: &[u8]) {
+        let text = String::from_utf8_lossy(data).replace('\r', "");
+        let mut parts = text.split('\n');
+        if self.nus_line_open {
+            if let (Some(last), Some(first)) = (self.nus_lines.last_mut(), parts.next()) {
+                last.push_str(first);
+            }
+        }
+        self.nus_lines.extend(parts.map(String::from));
+        self.nus_line_open = !text.ends_with('\n');
+        if !self.nus_line_open {
+            self.nus_lines.pop();
+        }
+    }
+
+    /// Re-reads the properties of the selected device so that the raw properties
+    /// view reflects the most recent advertisement.
+    pub async fn refresh_properties(&mut self) {
+        if let Some(device_info) = self.selected_index().and_then(|i| self.devices.get_mut(i)) {
+            if let Some(device) = &device_info.device {
+                if let Ok(Some(properties)) = device.properties().await {
+                    device_info.properties = properties;
+                }
+            }
+        }
+    }
+
+    /// Exports the device list once `auto_export_interval` has passed since the last automatic
+    /// export. The file is written on a blocking task so a slow disk doesn't hold up the UI,
+    /// and the outcome is reported as a `DeviceData::Checkpoint`.
+    pub fn auto_export_tick(&mut self) {
+        let Some(interval) = self.auto_export_interval else {
+            return;
+        };
+        if self.auto_exported_at.elapsed() < interval {
+            return;
+        }
+        self.auto_exported_at = Instant::now();
+
+        let rows = self.devices_csv_rows(self.devices.iter());
+        let file_path = self.export_path("_checkpoint");
+        let tx_clone = self.tx.clone();
+        tokio::spawn(async move {
+            let count = rows.len();
+            let result = tokio::task::spawn_blocking(move || {
+                write_csv(&file_path, rows)
+                    .map(|_| format!("Checkpoint: {} devices exported to {}", count, file_path))
+            })
+            .await
+            .unwrap_or_else(|e| Err(Error::Io(e.into())));
+            let _ = tx_clone.send(DeviceData::Checkpoint(result)).await;
+        });
+    }
+
+    /// Returns the key redacting addresses in exports, when they are anonymized.
+    fn export_key(&self) -> Option<&RandomState> {
+        self.anonymize_exports.then_some(&self.anonymize_key)
+    }
+
+    pub fn get_devices_csv(&self) -> Result<String, Error> {
+        let file_path = self.write_devices_csv(self.devices.iter(), "")?;
+        Ok(format!("Devices exported to {}.", file_path))
+    }
+
+    /// Exports only the devices marked in the device table.
+    pub fn get_selected_devices_csv(&self) -> Result<String, Error> {
+        if self.selected_ids.is_empty() {
+            return Ok("No devices selected. Press space to select devices.".to_string());
+        }
+        let devices = self
+            .devices
+            .iter()
+            .filter(|device| self.selected_ids.contains(&device.id));
+        let file_path = self.write_devices_csv(devices, "_selected")?;
+        Ok(format!(
+            "{} selected devices exported to {}.",
+            self.selected_ids.len(),
+            file_path
+        ))
+    }
+
+    /// Copies the visible devices, in table order and with the table's columns, to the
+    /// clipboard as a markdown table. Without a clipboard the table is written to a
+    /// temporary file instead.
+    pub fn copy_visible_devices(&mut self) -> Result<String, Error> {
+        let row = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        let mut table = row(self
+            .columns
+            .iter()
+            .map(|column| column.title().to_owned())
+            .collect());
+        table.push_str(&row(self
+            .columns
+            .iter()
+            .map(|_| "---".to_owned())
+            .collect()));
+        let devices = self.visible_devices();
+        for device in devices.iter() {
+            let distance = self.estimate_distance(device);
+            let device = exported(device, self.export_key());
+            table.push_str(&row(self
+                .columns
+                .iter()
+                .map(|column| column.value(&device, distance).replace('|', "\\|"))
+                .collect()));
+        }
+        let count = devices.len();
+
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        if let Some(clipboard) = &mut self.clipboard {
+            if clipboard.set_text(table.clone()).is_ok() {
+                return Ok(format!("{} devices copied to the clipboard.", count));
+            }
+        }
+
         let now = chrono::Local::now();
         let timestamp = now.format("%Y-%m-%d_%H-%M-%S").to_string();
-        let file_path = format!("btlescan_{}.csv", timestamp);
-        let file = std::fs::File::create(file_path).expect("Unable to create file");
-        let mut wtr = csv::Writer::from_writer(file);
-        for device in &self.devices {
-            wtr.serialize(DeviceCsv {
-                id: device.id.clone(),
-                name: device.name.clone(),
-                tx_power: device.tx_power.clone(),
-                address: device.address.clone(),
-                rssi: device.rssi.clone(),
-            })?;
-        }
-        wtr.flush()?;
-        Ok("Devices exported to a CSV file in the current directory.".to_string())
+        let file_path = std::env::temp_dir().join(format!("btlescan_{}.md", timestamp));
+        std::fs::write(&file_path, table)?;
+        Ok(format!(
+            "No clipboard available, {} devices written to {}.",
+            count,
+            file_path.display()
+        ))
+    }
+
+    /// Writes `devices` to a CSV file in the current directory, returning its path. The file of
+    /// the session is overwritten on every export, unless `timestamped_exports` is set. `suffix`
+    /// tells apart the files of different kinds of exports.
+    fn write_devices_csv<'a>(
+        &self,
+        devices: impl Iterator<Item = &'a DeviceInfo>,
+        suffix: &str,
+    ) -> Result<String, Error> {
+        let file_path = self.export_path(suffix);
+        write_csv(&file_path, self.devices_csv_rows(devices))?;
+        Ok(file_path)
+    }
+
+    /// Returns the path of an export: the file of the session, or a new timestamped file with
+    /// `--timestamped-exports`, with `suffix` appended to its name.
+    fn export_path(&self, suffix: &str) -> String {
+        let timestamp = if self.timestamped_exports {
+            chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string()
+        } else {
+            self.session_timestamp.clone()
+        };
+        format!("btlescan_{}{}.csv", timestamp, suffix)
+    }
+
+    /// Returns the rows exported for the devices, with their notes and with addresses redacted
+    /// when anonymizing exports.
+    fn devices_csv_rows<'a>(
+        &self,
+        devices: impl Iterator<Item = &'a DeviceInfo>,
+    ) -> Vec<DeviceCsv> {
+        devices
+            .map(|device| {
+                let note = self.notes.get(&device.id).cloned().unwrap_or_default();
+                let device = exported(device, self.export_key());
+                DeviceCsv {
+                    id: device.id.clone(),
+                    name: device.name.clone(),
+                    tx_power: device.tx_power.clone(),
+                    address: device.address.clone(),
+                    rssi: device.rssi.clone(),
+                    manufacturer_data: manufacturer_data_csv(
+                        &device.manufacturer_data,
+                        self.hex_format,
+                    ),
+                    note,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Writes the rows to a CSV file at `file_path`, replacing it.
+fn write_csv(file_path: &str, rows: Vec<DeviceCsv>) -> Result<(), Error> {
+    let file = std::fs::File::create(file_path)?;
+    let mut wtr = csv::Writer::from_writer(file);
+    for row in rows {
+        wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use btleplug::api::{BDAddr, PeripheralProperties};
+    use clap::Parser;
+
+    use super::*;
+
+    /// Creates an app with the default arguments and settings, as `config_dir` gives no
+    /// configuration directory in tests.
+    fn app() -> App {
+        App::new(&Args::parse_from(["btlescan"]))
+    }
+
+    fn device(last_octet: u8, name: &str, rssi: i16) -> DeviceInfo {
+        DeviceInfo::new_synthetic(PeripheralProperties {
+            address: BDAddr::from([0x02, 0x00, 0x00, 0x00, 0x00, last_octet]),
+            local_name: Some(name.to_owned()),
+            rssi: Some(rssi),
+            ..PeripheralProperties::default()
+        })
+    }
+
+    fn visible_names(app: &App) -> Vec<&str> {
+        app.visible_devices()
+            .iter()
+            .map(|device| device.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn sort_key_compares_rssi_in_either_direction() {
+        let (near, far) = (device(1, "Near", -40), device(2, "Far", -80));
+        assert_eq!(SortKey::Rssi.compare(&far, &near, true), CmpOrdering::Less);
+        assert_eq!(
+            SortKey::Rssi.compare(&far, &near, false),
+            CmpOrdering::Greater
+        );
+    }
+
+    #[test]
+    fn sort_key_compares_names() {
+        let (a, b) = (device(1, "Alpha", -40), device(2, "Beta", -40));
+        assert_eq!(SortKey::Name.compare(&a, &b, true), CmpOrdering::Less);
+        assert_eq!(SortKey::Name.compare(&a, &b, false), CmpOrdering::Greater);
+    }
+
+    #[test]
+    fn visible_indices_follow_the_sort() {
+        let mut app = app();
+        app.devices = vec![
+            device(1, "Far", -80),
+            device(2, "Near", -40),
+            device(3, "Middle", -60),
+        ];
+        app.sort_key = SortKey::Rssi;
+        app.sort_ascending = false;
+        assert_eq!(app.visible_indices(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn visible_indices_keep_detection_order_for_the_default_sort() {
+        let mut app = app();
+        app.devices = vec![device(1, "B", -80), device(2, "A", -40)];
+        app.sort_key = SortKey::Detected;
+        app.sort_ascending = true;
+        assert_eq!(visible_names(&app), vec!["B", "A"]);
+        app.sort_ascending = false;
+        assert_eq!(visible_names(&app), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn visible_indices_apply_the_device_filters() {
+        let mut app = app();
+        let mut with_data = device(1, "Beacon", -50);
+        with_data.manufacturer_data.insert(0x004C, vec![0x02, 0x15]);
+        app.devices = vec![device(2, "Plain", -50), with_data];
+        app.toggle_device_filter(DeviceFilter::ManufacturerData);
+        assert_eq!(visible_names(&app), vec!["Beacon"]);
+        app.toggle_device_filter(DeviceFilter::ManufacturerData);
+        assert_eq!(app.visible_indices().len(), 2);
+    }
+
+    #[test]
+    fn visible_indices_pin_favorites_and_filter_them_alone() {
+        let mut app = app();
+        app.devices = vec![device(1, "Other", -40), device(2, "Favorite", -80)];
+        app.sort_key = SortKey::Rssi;
+        app.sort_ascending = false;
+        app.favorites.insert(app.devices[1].id.clone());
+        assert_eq!(visible_names(&app), vec!["Favorite", "Other"]);
+        app.favorites_only = true;
+        assert_eq!(visible_names(&app), vec!["Favorite"]);
+    }
+
+    #[test]
+    fn sort_key_orders_tied_rssi_by_name_then_id() {
+        let mut app = app();
+        app.devices = vec![
+            device(4, "Charlie", -60),
+            device(3, "Alpha", -60),
+            device(1, "Bravo", -60),
+            device(2, "Alpha", -60),
+            device(5, "Strong", -40),
+        ];
+        app.sort_key = SortKey::Rssi;
+        app.sort_ascending = false;
+        assert_eq!(app.visible_indices(), vec![4, 3, 1, 2, 0]);
+        // Only the RSSI is reversed, the ties stay in ascending order
+        app.sort_ascending = true;
+        assert_eq!(app.visible_indices(), vec![3, 1, 2, 0, 4]);
+    }
+
+    #[test]
+    fn sort_key_order_of_tied_rssi_does_not_depend_on_detection_order() {
+        let mut app = app();
+        app.sort_key = SortKey::Rssi;
+        app.devices = vec![device(1, "B", -60), device(2, "A", -60)];
+        let order: Vec<String> = app.visible_devices().iter().map(|d| d.id.clone()).collect();
+        app.devices.reverse();
+        let reversed: Vec<String> = app.visible_devices().iter().map(|d| d.id.clone()).collect();
+        assert_eq!(order, reversed);
+    }
+
+    #[test]
+    fn add_device_marks_the_screen_dirty() {
+        let mut app = app();
+        app.dirty = false;
+        app.add_device(device(1, "New", -50));
+        assert!(app.dirty);
+
+        app.dirty = false;
+        app.add_device(device(1, "New", -45));
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn eviction_of_the_oldest_removes_the_least_recently_seen_device() {
+        let mut app = app();
+        app.max_devices = 2;
+        app.eviction_policy = EvictionPolicy::Oldest;
+        app.add_device(device(1, "Refreshed", -80));
+        app.add_device(device(2, "Stale", -40));
+        let stale = app.devices[1].id.clone();
+        app.last_seen
+            .insert(stale, Instant::now() - Duration::from_secs(10));
+        app.add_device(device(3, "New", -90));
+        assert_eq!(visible_names(&app), vec!["Refreshed", "New"]);
+        assert_eq!(app.evicted_devices, 1);
+    }
+
+    #[test]
+    fn eviction_of_the_weakest_removes_the_weakest_signal() {
+        let mut app = app();
+        app.max_devices = 2;
+        app.eviction_policy = EvictionPolicy::Weakest;
+        app.add_device(device(1, "Near", -40));
+        app.add_device(device(2, "Far", -80));
+        app.add_device(device(3, "Middle", -60));
+        assert_eq!(visible_names(&app), vec!["Near", "Middle"]);
+        // A new device weaker than all the others is the one evicted
+        app.add_device(device(4, "Farther", -90));
+        assert_eq!(visible_names(&app), vec!["Near", "Middle"]);
+        assert_eq!(app.evicted_devices, 2);
+    }
+
+    #[test]
+    fn eviction_spares_favorites() {
+        let mut app = app();
+        app.max_devices = 2;
+        app.eviction_policy = EvictionPolicy::Weakest;
+        app.add_device(device(1, "Favorite", -90));
+        app.favorites.insert(app.devices[0].id.clone());
+        app.add_device(device(2, "Near", -40));
+        app.add_device(device(3, "Far", -80));
+        assert_eq!(visible_names(&app), vec!["Favorite", "Near"]);
+    }
+
+    fn type_name_filter(app: &mut App, pattern: &str) {
+        app.input_buffer = pattern.to_owned();
+        app.on_input_changed();
+    }
+
+    #[test]
+    fn name_filter_applies_a_valid_pattern() {
+        let mut app = app();
+        app.devices = vec![
+            device(1, "Polar H10", -50),
+            device(2, "Garmin HRM", -50),
+            device(3, "My Polar", -50),
+        ];
+        app.edit_name_filter();
+        type_name_filter(&mut app, "^Polar|^Garmin");
+        assert_eq!(visible_names(&app), vec!["Polar H10", "Garmin HRM"]);
+        assert_eq!(app.name_filter_error, None);
+        app.save_name_filter();
+        assert_eq!(visible_names(&app), vec!["Polar H10", "Garmin HRM"]);
+    }
+
+    #[test]
+    fn name_filter_keeps_the_previous_pattern_when_invalid() {
+        let mut app = app();
+        app.devices = vec![device(1, "Polar H10", -50), device(2, "Garmin HRM", -50)];
+        app.edit_name_filter();
+        type_name_filter(&mut app, "^Polar");
+        type_name_filter(&mut app, "^Polar(");
+        assert!(app.name_filter_error.is_some());
+        assert_eq!(app.name_filter.as_ref().map(Regex::as_str), Some("^Polar"));
+        assert_eq!(visible_names(&app), vec!["Polar H10"]);
+    }
+
+    #[test]
+    fn name_filter_is_restored_when_editing_is_cancelled() {
+        let mut app = app();
+        app.edit_name_filter();
+        type_name_filter(&mut app, "^Polar");
+        app.save_name_filter();
+        app.edit_name_filter();
+        type_name_filter(&mut app, "^Garmin");
+        app.cancel_name_filter();
+        assert_eq!(app.name_filter.as_ref().map(Regex::as_str), Some("^Polar"));
+    }
+
+    #[test]
+    fn pausing_and_resuming_keeps_the_sort_and_filters() {
+        let mut app = app();
+        app.devices = vec![
+            device(1, "Polar H10", -70),
+            device(2, "Garmin HRM", -50),
+            device(3, "Polar Verity", -40),
+        ];
+        app.sort_key = SortKey::Rssi;
+        app.sort_ascending = false;
+        app.edit_name_filter();
+        type_name_filter(&mut app, "^Polar");
+        app.save_name_filter();
+        app.table_state.select(Some(1));
+        let paused = app.pause_status.load(Ordering::SeqCst);
+
+        app.toggle_pause();
+        assert_eq!(app.pause_status.load(Ordering::SeqCst), !paused);
+        app.toggle_pause();
+        assert_eq!(app.pause_status.load(Ordering::SeqCst), paused);
+
+        assert!(app.sort_key == SortKey::Rssi && !app.sort_ascending);
+        assert_eq!(app.name_filter.as_ref().map(Regex::as_str), Some("^Polar"));
+        assert_eq!(visible_names(&app), vec!["Polar Verity", "Polar H10"]);
+        assert_eq!(
+            app.selected_device().map(|device| device.name.as_str()),
+            Some("Polar H10")
+        );
+    }
+
+    /// Puts the app in the state `connect_device` leaves it in, without connecting.
+    fn start_connecting(app: &mut App, device: DeviceInfo) -> u64 {
+        app.connect_attempt += 1;
+        app.is_loading = true;
+        app.connect_started = Some(Instant::now());
+        app.inspected_device = Some(device);
+        app.connect_attempt
+    }
+
+    fn characteristics() -> Vec<Characteristic> {
+        vec![Characteristic {
+            uuid: BATTERY_LEVEL_UUID,
+            properties: CharPropFlags::READ,
+            descriptors: Vec::new(),
+            service: Uuid::nil(),
+        }]
+    }
+
+    #[tokio::test]
+    async fn unrelated_errors_during_a_connect_keep_its_characteristics() {
+        let mut app = app();
+        let device = device(1, "Sensor", -50);
+        let attempt = start_connecting(&mut app, device.clone());
+
+        app.on_device_data(DeviceData::Error(
+            Error::CharacteristicNotFound(Uuid::nil()),
+        ));
+        assert!(app.error_view);
+        assert!(app.is_loading && app.connect_started.is_some());
+
+        app.on_device_data(DeviceData::Characteristics(attempt, characteristics()));
+        assert!(!app.is_loading && app.connect_started.is_none());
+        assert!(app.inspect_view);
+        assert_eq!(app.selected_characteristics.len(), 1);
+        assert!(app.connections.contains_key(&device.id));
+    }
+
+    #[tokio::test]
+    async fn results_of_a_cancelled_connect_are_ignored() {
+        let mut app = app();
+        let attempt = start_connecting(&mut app, device(1, "Sensor", -50));
+        app.cancel_connect();
+
+        app.on_device_data(DeviceData::Characteristics(attempt, characteristics()));
+        app.on_device_data(DeviceData::ConnectError(attempt, Error::ConnectTimeout));
+        assert!(app.connections.is_empty());
+        assert!(!app.inspect_view && !app.error_view);
+    }
+
+    #[tokio::test]
+    async fn a_failed_connect_stops_loading() {
+        let mut app = app();
+        let attempt = start_connecting(&mut app, device(1, "Sensor", -50));
+        app.on_device_data(DeviceData::ConnectError(attempt, Error::ConnectTimeout));
+        assert!(app.error_view);
+        assert!(!app.is_loading && app.connect_started.is_none());
+    }
+
+    #[test]
+    fn device_updates_keep_the_selected_device_selected() {
+        let mut app = app();
+        app.sort_key = SortKey::Rssi;
+        app.sort_ascending = false;
+        app.add_device(device(1, "Near", -40));
+        app.add_device(device(2, "Far", -80));
+        app.table_state.select(Some(0));
+
+        app.on_device_updates(vec![
+            DeviceData::DeviceInfo(Box::new(device(2, "Far", -30))),
+            DeviceData::DeviceInfo(Box::new(device(3, "Nearest", -20))),
+        ]);
+        assert_eq!(visible_names(&app), vec!["Nearest", "Far", "Near"]);
+        assert_eq!(app.table_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn device_updates_leave_no_selection_alone() {
+        let mut app = app();
+        app.table_state.select(None);
+        app.on_device_updates(vec![DeviceData::DeviceInfo(Box::new(device(
+            1, "New", -40,
+        )))]);
+        assert_eq!(app.table_state.selected(), None);
+    }
+
+    #[test]
+    fn app_starts_with_the_default_settings() {
+        let app = app();
+        assert!(app.favorites.is_empty());
+        assert!(app.columns == crate::structs::DEFAULT_COLUMNS);
+        assert!(!app.dense_layout);
+        assert!(app.logs.is_empty());
     }
 }
@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+
+use crate::utils::DEFAULT_REFERENCE_POWER;
+
+/// Parses a smoothing factor, which must be above 0 and at most 1.
+fn parse_smoothing(value: &str) -> Result<f32, String> {
+    let factor: f32 = value
+        .parse()
+        .map_err(|_| format!("`{}` isn't a number", value))?;
+    if factor > 0.0 && factor <= 1.0 {
+        Ok(factor)
+    } else {
+        Err("the smoothing factor must be above 0 and at most 1".to_owned())
+    }
+}
+
+/// Which device is evicted once there are more than `--max-devices`.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum EvictionPolicy {
+    /// The device seen least recently.
+    Oldest,
+    /// The device with the weakest RSSI, or without one.
+    Weakest,
+}
+
+/// How bytes are written as hex in exports.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum HexFormat {
+    /// Space separated uppercase bytes, as shown in the UI, e.g. `DE AD BE EF`.
+    Spaced,
+    /// Lowercase digits without separators, e.g. `deadbeef`.
+    Compact,
+    /// Compact with a `0x` prefix, e.g. `0xdeadbeef`.
+    Prefixed,
+}
+
+/// A cross-platform CLI to scan and inspect Bluetooth Low Energy devices.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Open with scanning paused; nothing is scanned until `s` is pressed.
+    #[arg(long)]
+    pub start_paused: bool,
+
+    /// Milliseconds between checks for input and updates while scanning or handling input. The
+    /// screen is only redrawn when something changed.
+    #[arg(long, default_value_t = 100)]
+    pub tick_rate: u64,
+
+    /// Milliseconds between redraws while connecting or loading, which keep the spinner moving
+    /// whether or not any input arrives.
+    #[arg(long, default_value_t = 80)]
+    pub loading_tick_rate: u64,
+
+    /// Milliseconds between checks once nothing has changed for a few seconds, which is also
+    /// how often an unchanged screen is redrawn to keep timers current.
+    #[arg(long, default_value_t = 1000)]
+    pub idle_tick_rate: u64,
+
+    /// Number of pending advertisements buffered for the UI before new ones are dropped.
+    #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub channel_capacity: u64,
+
+    /// Feed synthetic devices instead of scanning, to try the UI without Bluetooth hardware.
+    #[arg(long)]
+    pub demo: bool,
+
+    /// Seconds to wait for a device to connect before giving up.
+    #[arg(long, default_value_t = 10)]
+    pub connect_timeout: u64,
+
+    /// Seconds a value read from a characteristic is reused before reading it again reads the
+    /// device. A refresh always reads the device.
+    #[arg(long, default_value_t = 10)]
+    pub read_cache_ttl: u64,
+
+    /// Print the Bluetooth adapters found and exit, without opening the UI.
+    #[arg(long)]
+    pub list_adapters: bool,
+
+    /// Quit without asking first while devices are connected. They are still disconnected.
+    #[arg(long)]
+    pub no_confirm: bool,
+
+    /// Write each CSV export to a new timestamped file, instead of overwriting the file of the
+    /// session.
+    #[arg(long)]
+    pub timestamped_exports: bool,
+
+    /// Export the device list every this many minutes, so a crash doesn't lose the session.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub auto_export_interval: Option<u64>,
+
+    /// How manufacturer data is written as hex in CSV exports, recordings, the log file and
+    /// the status server.
+    #[arg(long, value_enum, default_value_t = HexFormat::Spaced)]
+    pub hex_format: HexFormat,
+
+    /// Seconds to wait for a Bluetooth adapter to appear before giving up on scanning.
+    #[arg(long, default_value_t = 5)]
+    pub adapter_timeout: u64,
+
+    /// Maximum number of devices kept in the table. Once exceeded, a device that isn't a
+    /// favorite or connected is evicted, chosen by `--evict`.
+    #[arg(long, default_value_t = 500, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_devices: u64,
+
+    /// Which device to evict beyond `--max-devices`.
+    #[arg(long, value_enum, default_value_t = EvictionPolicy::Oldest)]
+    pub evict: EvictionPolicy,
+
+    /// Path-loss exponent used to estimate distance from RSSI (2.0 in free space, 2.7 - 4.0
+    /// indoors).
+    #[arg(long, default_value_t = 2.0)]
+    pub path_loss_exponent: f32,
+
+    /// Expected RSSI at 1 meter for devices that do not advertise their TX power.
+    #[arg(long, default_value_t = DEFAULT_REFERENCE_POWER, allow_negative_numbers = true)]
+    pub reference_power: i16,
+
+    /// Smooth the RSSI shown in the device table with an exponential moving average, weighing
+    /// each new reading by this factor between 0 and 1 (e.g. 0.2). Off by default.
+    #[arg(long, value_parser = parse_smoothing)]
+    pub rssi_smoothing: Option<f32>,
+
+    /// Draw the UI inline rather than on the alternate screen, so output written to the
+    /// terminal, such as panics, stays visible after quitting.
+    #[arg(long, alias = "debug-tty")]
+    pub no_alt_screen: bool,
+
+    /// Append every entry of the message log to this file as it is logged, for debugging.
+    #[arg(long)]
+    pub debug_log: Option<PathBuf>,
+
+    /// Append every discovered device to this CSV file as it is seen.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+
+    /// Rotate the log file to `<log-file>.1` once it grows beyond this many bytes.
+    #[arg(long, requires = "log_file")]
+    pub log_max_bytes: Option<u64>,
+
+    /// Serve the devices and the scanner state as JSON on this port of localhost, at
+    /// `/devices` and `/status`.
+    #[arg(long, value_name = "PORT")]
+    pub serve: Option<u16>,
+
+    /// Redact device addresses in exports, recordings, the log file and clipboard copies.
+    #[arg(long)]
+    pub anonymize: bool,
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashSet, fs, io, path::PathBuf};
+
+use crate::{
+    error::Error,
+    keymap::Keymap,
+    structs::{Column, DEFAULT_COLUMNS},
+};
+
+/// Returns the directory where btlescan keeps its settings, e.g. `~/.config/btlescan`.
+#[cfg(not(test))]
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("btlescan"))
+}
+
+/// Tests run without a configuration directory, so they get the default settings whatever is
+/// configured on the machine, and never overwrite them.
+#[cfg(test)]
+pub fn config_dir() -> Option<PathBuf> {
+    None
+}
+
+fn favorites_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("favorites"))
+}
+
+/// Loads the ids of the favorite devices, one per line. A missing file means no favorites.
+pub fn load_favorites() -> HashSet<String> {
+    favorites_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|contents| {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Loads the columns of the device table, one name per line in display order. Unknown names
+/// are skipped, and a missing or empty file means the default columns.
+pub fn load_columns() -> Vec<Column> {
+    let columns: Vec<Column> = config_dir()
+        .and_then(|dir| fs::read_to_string(dir.join("columns")).ok())
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| Column::from_name(line.trim()))
+                .collect()
+        })
+        .unwrap_or_default();
+    if columns.is_empty() {
+        DEFAULT_COLUMNS.to_vec()
+    } else {
+        columns
+    }
+}
+
+fn layout_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("layout"))
+}
+
+/// Loads whether the dense layout was chosen, written as `dense` in the `layout` file. A
+/// missing file means the normal layout.
+pub fn load_dense_layout() -> bool {
+    layout_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .is_some_and(|contents| contents.trim() == "dense")
+}
+
+/// Saves the chosen layout, `dense` or `normal`.
+pub fn save_dense_layout(dense: bool) -> io::Result<()> {
+    let path = layout_path().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "No configuration directory found")
+    })?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, if dense { "dense\n" } else { "normal\n" })
+}
+
+/// Loads the key bindings from the `keys` file, see `Keymap::parse`. A missing file means the
+/// default keys.
+pub fn load_keymap() -> Result<Keymap, Error> {
+    match config_dir().and_then(|dir| fs::read_to_string(dir.join("keys")).ok()) {
+        Some(contents) => Keymap::parse(&contents),
+        None => Ok(Keymap::default()),
+    }
+}
+
+/// Saves the ids of the favorite devices, one per line.
+pub fn save_favorites(favorites: &HashSet<String>) -> io::Result<()> {
+    let path = favorites_path().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "No configuration directory found")
+    })?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut ids: Vec<&String> = favorites.iter().collect();
+    ids.sort();
+    let contents: String = ids.iter().map(|id| format!("{}\n", id)).collect();
+    fs::write(path, contents)
+}
@@ -0,0 +1,72 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+};
+
+use tokio::sync::mpsc;
+
+use crate::{app::DeviceData, error::Error, structs::AdvertisementCsv};
+
+/// Opens `path` for appending, writing the CSV header only when the file is new or empty.
+fn open_writer(path: &Path) -> std::io::Result<(csv::Writer<File>, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    let writer = csv::WriterBuilder::new()
+        .has_headers(size == 0)
+        .from_writer(file);
+    Ok((writer, size))
+}
+
+/// Writes one record and flushes it, returning the new size of the file.
+fn write_record(
+    writer: &mut csv::Writer<File>,
+    record: &AdvertisementCsv,
+    path: &Path,
+) -> csv::Result<u64> {
+    writer.serialize(record)?;
+    writer.flush()?;
+    Ok(fs::metadata(path)?.len())
+}
+
+/// Appends every advertisement received on `rx` to the CSV file at `path`. When `max_bytes` is
+/// set, the file is rotated to `<path>.1` once it grows beyond that size. On a write failure
+/// (e.g. the disk is full) the error is reported through `tx` and logging stops, leaving the
+/// rest of the application running. The file is written synchronously, so this runs on a
+/// blocking thread rather than holding up an async worker for every advertisement.
+pub fn discovery_log(
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    mut rx: mpsc::UnboundedReceiver<AdvertisementCsv>,
+    tx: mpsc::Sender<DeviceData>,
+) {
+    let (mut writer, mut size) = match open_writer(&path) {
+        Ok(opened) => opened,
+        Err(e) => {
+            let _ = tx.blocking_send(DeviceData::Error(Error::LogOpen(e)));
+            return;
+        }
+    };
+
+    while let Some(record) = rx.blocking_recv() {
+        if max_bytes.is_some_and(|max_bytes| size >= max_bytes) {
+            let mut rotated = path.clone().into_os_string();
+            rotated.push(".1");
+            let reopened = fs::rename(&path, rotated).and_then(|_| open_writer(&path));
+            match reopened {
+                Ok((new_writer, _)) => writer = new_writer,
+                Err(e) => {
+                    let _ = tx.blocking_send(DeviceData::Error(Error::LogRotate(e)));
+                    return;
+                }
+            }
+        }
+
+        match write_record(&mut writer, &record, &path) {
+            Ok(new_size) => size = new_size,
+            Err(e) => {
+                let _ = tx.blocking_send(DeviceData::Error(Error::LogWrite(e)));
+                return;
+            }
+        }
+    }
+}
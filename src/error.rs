@@ -0,0 +1,80 @@
+use std::io;
+
+use uuid::Uuid;
+
+/// The errors of scanning, connecting to devices, and exporting what was found.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("No adapters found")]
+    AdapterNotFound,
+    #[error(
+        "Bluetooth permission denied. Grant Bluetooth permission in System Settings > Privacy"
+    )]
+    PermissionDenied,
+    #[error("Scanning failure: {0}")]
+    Scan(btleplug::Error),
+    #[error("Invalid address or UUID: {0}")]
+    InvalidAddress(String),
+    #[error("Device not found: {0}")]
+    DeviceNotFound(String),
+    /// The device has no peripheral to connect to.
+    #[error("Device not found")]
+    NoPeripheral,
+    #[error("Connection error: {0}")]
+    Connect(btleplug::Error),
+    #[error("Connection timed out")]
+    ConnectTimeout,
+    #[error("Disconnect error: {0}")]
+    Disconnect(btleplug::Error),
+    #[error("Discovery error: {0}")]
+    Discovery(btleplug::Error),
+    #[error("Characteristic not found: {0}")]
+    CharacteristicNotFound(Uuid),
+    #[error("Nordic UART characteristics not found")]
+    NusNotFound,
+    #[error("Read error: {0}")]
+    Read(btleplug::Error),
+    #[error("Write error: {0}")]
+    WriteRejected(btleplug::Error),
+    #[error("Notification error: {0}")]
+    Notification(btleplug::Error),
+    #[error("Subscribe error: {0}")]
+    Subscribe(btleplug::Error),
+    #[error("Unable to open log file: {0}")]
+    LogOpen(io::Error),
+    #[error("Unable to rotate log file: {0}")]
+    LogRotate(io::Error),
+    #[error("Unable to open debug log: {0}")]
+    DebugLog(io::Error),
+    #[error("Logging stopped, unable to write log file: {0}")]
+    LogWrite(csv::Error),
+    #[error("Invalid key binding, using the default keys: {0}")]
+    Keymap(String),
+    #[error("Unable to start the status server: {0}")]
+    Serve(io::Error),
+    #[error(transparent)]
+    Bluetooth(#[from] btleplug::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+}
+
+impl Error {
+    /// Wraps an error of setting up or running the scan, telling a missing Bluetooth permission
+    /// apart from other failures. CoreBluetooth and WinRT report it as such, while BlueZ passes
+    /// the D-Bus error through.
+    pub fn scan(error: btleplug::Error) -> Self {
+        match error {
+            btleplug::Error::PermissionDenied => Error::PermissionDenied,
+            btleplug::Error::Other(ref e)
+                if ["AccessDenied", "NotPermitted", "NotAuthorized"]
+                    .iter()
+                    .any(|name| e.to_string().contains(name)) =>
+            {
+                Error::PermissionDenied
+            }
+            error => Error::Scan(error),
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+
+use crossterm::event::KeyCode;
+
+use crate::error::Error;
+
+/// Where a binding applies. Bindings of the inspect overlay and the connections panel take
+/// precedence over global ones while those are open, so they may reuse a global key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Context {
+    Global,
+    Inspect,
+    Connections,
+}
+
+/// The actions of the normal mode that can be bound to keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Pause,
+    Focus,
+    FilterManufacturerData,
+    FilterServices,
+    Anonymize,
+    Freeze,
+    Favorite,
+    FavoritesOnly,
+    ManufacturerAscii,
+    Payload,
+    ShortUuids,
+    Sort,
+    SortDirection,
+    Note,
+    Reconnect,
+    RestartScan,
+    ConnectAddress,
+    GoTo,
+    NameFilter,
+    LogFilter,
+    Record,
+    Export,
+    ExportSelected,
+    Copy,
+    Select,
+    SelectAll,
+    ClearSelection,
+    Connections,
+    RawProperties,
+    DenseLayout,
+    Details,
+    Alert,
+    ServicePicker,
+    Locate,
+    /// Closes the topmost overlay, or connects to the selected device.
+    Connect,
+    NavDown,
+    NavUp,
+    Read,
+    Refresh,
+    AutoRead,
+    Write,
+    WriteType,
+    Verify,
+    Notify,
+    PropertyFilter,
+    PropertiesHex,
+    NusTerminal,
+    Disconnect,
+}
+
+/// Every action with its name in the configuration and its default keys.
+const ACTIONS: [(Action, &str, &[KeyCode]); 49] = [
+    (Action::Quit, "quit", &[KeyCode::Char('q')]),
+    (Action::Pause, "pause", &[KeyCode::Char('s')]),
+    (Action::Focus, "focus", &[KeyCode::Tab]),
+    (
+        Action::FilterManufacturerData,
+        "filter_manufacturer_data",
+        &[KeyCode::Char('B')],
+    ),
+    (
+        Action::FilterServices,
+        "filter_services",
+        &[KeyCode::Char('S')],
+    ),
+    (Action::Anonymize, "anonymize", &[KeyCode::Char('X')]),
+    (Action::Freeze, "freeze", &[KeyCode::Char('z')]),
+    (Action::Favorite, "favorite", &[KeyCode::Char('f')]),
+    (
+        Action::FavoritesOnly,
+        "favorites_only",
+        &[KeyCode::Char('F')],
+    ),
+    (
+        Action::ManufacturerAscii,
+        "manufacturer_ascii",
+        &[KeyCode::Char('m')],
+    ),
+    (Action::Payload, "payload", &[KeyCode::Char('M')]),
+    (Action::ShortUuids, "short_uuids", &[KeyCode::Char('u')]),
+    (Action::Sort, "sort", &[KeyCode::Char('o')]),
+    (
+        Action::SortDirection,
+        "sort_direction",
+        &[KeyCode::Char('O')],
+    ),
+    (Action::Note, "note", &[KeyCode::Char('N')]),
+    (Action::Reconnect, "reconnect", &[KeyCode::Char('R')]),
+    (Action::RestartScan, "restart_scan", &[KeyCode::Char('L')]),
+    (
+        Action::ConnectAddress,
+        "connect_address",
+        &[KeyCode::Char('c')],
+    ),
+    (Action::GoTo, "go_to", &[KeyCode::Char(':')]),
+    (Action::NameFilter, "name_filter", &[KeyCode::Char('/')]),
+    (Action::LogFilter, "log_filter", &[KeyCode::Char('?')]),
+    (Action::Record, "record", &[KeyCode::Char('r')]),
+    (Action::Export, "export", &[KeyCode::Char('e')]),
+    (
+        Action::ExportSelected,
+        "export_selected",
+        &[KeyCode::Char('E')],
+    ),
+    (Action::Copy, "copy", &[KeyCode::Char('y')]),
+    (Action::Select, "select", &[KeyCode::Char(' ')]),
+    (Action::SelectAll, "select_all", &[KeyCode::Char('a')]),
+    (
+        Action::ClearSelection,
+        "clear_selection",
+        &[KeyCode::Char('A')],
+    ),
+    (Action::Connections, "connections", &[KeyCode::Char('P')]),
+    (
+        Action::RawProperties,
+        "raw_properties",
+        &[KeyCode::Char('D')],
+    ),
+    (Action::DenseLayout, "dense_layout", &[KeyCode::Char('l')]),
+    (Action::Details, "details", &[KeyCode::Char('i')]),
+    (Action::Alert, "alert", &[KeyCode::Char('b')]),
+    (
+        Action::ServicePicker,
+        "service_picker",
+        &[KeyCode::Char('U')],
+    ),
+    (Action::Locate, "locate", &[KeyCode::Char('g')]),
+    (Action::Connect, "connect", &[KeyCode::Enter]),
+    (
+        Action::NavDown,
+        "nav_down",
+        &[KeyCode::Down, KeyCode::Char('j')],
+    ),
+    (Action::NavUp, "nav_up", &[KeyCode::Up, KeyCode::Char('k')]),
+    (Action::Read, "read", &[KeyCode::Char('r')]),
+    (Action::Refresh, "refresh", &[KeyCode::Char('R')]),
+    (Action::AutoRead, "auto_read", &[KeyCode::Char('a')]),
+    (Action::Write, "write", &[KeyCode::Char('w')]),
+    (Action::WriteType, "write_type", &[KeyCode::Char('W')]),
+    (Action::Verify, "verify", &[KeyCode::Char('v')]),
+    (Action::Notify, "notify", &[KeyCode::Char('n')]),
+    (
+        Action::PropertyFilter,
+        "property_filter",
+        &[KeyCode::Char('p')],
+    ),
+    (
+        Action::PropertiesHex,
+        "properties_hex",
+        &[KeyCode::Char('x')],
+    ),
+    (Action::NusTerminal, "nus_terminal", &[KeyCode::Char('t')]),
+    (Action::Disconnect, "disconnect", &[KeyCode::Char('d')]),
+];
+
+impl Action {
+    fn from_name(name: &str) -> Option<Self> {
+        ACTIONS
+            .iter()
+            .find(|(_, action_name, _)| *action_name == name)
+            .map(|(action, _, _)| *action)
+    }
+
+    fn name(self) -> &'static str {
+        ACTIONS
+            .iter()
+            .find(|(action, _, _)| *action == self)
+            .map_or("", |(_, name, _)| name)
+    }
+
+    fn context(self) -> Context {
+        match self {
+            Action::Read
+            | Action::Refresh
+            | Action::AutoRead
+            | Action::Write
+            | Action::WriteType
+            | Action::Verify
+            | Action::Notify
+            | Action::PropertyFilter
+            | Action::PropertiesHex
+            | Action::NusTerminal => Context::Inspect,
+            Action::Disconnect => Context::Connections,
+            _ => Context::Global,
+        }
+    }
+}
+
+/// Parses a key as written in the configuration: a single character, or one of `space`,
+/// `comma`, `enter`, `tab`, `backspace`, `esc`, `up`, `down`, `left` and `right`.
+fn parse_key(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => return Some(KeyCode::Char(c)),
+        (None, _) => return None,
+        _ => {}
+    }
+    match key.to_ascii_lowercase().as_str() {
+        "space" => Some(KeyCode::Char(' ')),
+        "comma" => Some(KeyCode::Char(',')),
+        "enter" => Some(KeyCode::Enter),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "esc" => Some(KeyCode::Esc),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        _ => None,
+    }
+}
+
+/// Returns a key as it is written in the configuration.
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "space".to_owned(),
+        KeyCode::Char(',') => "comma".to_owned(),
+        KeyCode::Char(c) => c.to_string(),
+        key => format!("{:?}", key).to_ascii_lowercase(),
+    }
+}
+
+/// The keys bound to each action of the normal mode.
+pub struct Keymap {
+    bindings: HashMap<(Context, KeyCode), Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|(action, _, keys)| {
+                keys.iter()
+                    .map(move |key| ((action.context(), *key), *action))
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Parses bindings written one action per line, e.g. `nav_down = down, j`. Blank lines and
+    /// lines starting with `#` are skipped. The actions listed replace their default keys, and
+    /// the others keep them. Unknown actions or keys, and a key bound to two actions where both
+    /// apply, are rejected.
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        let mut keys: HashMap<Action, Vec<KeyCode>> = ACTIONS
+            .iter()
+            .map(|(action, _, keys)| (*action, keys.to_vec()))
+            .collect();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, line_keys) = line
+                .split_once('=')
+                .ok_or_else(|| Error::Keymap(format!("expected `action = key`: {}", line)))?;
+            let action = Action::from_name(name.trim())
+                .ok_or_else(|| Error::Keymap(format!("unknown action `{}`", name.trim())))?;
+            let line_keys = line_keys
+                .split(',')
+                .map(|key| parse_key(key.trim()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| {
+                    Error::Keymap(format!(
+                        "unknown key for `{}`: {}",
+                        name.trim(),
+                        line_keys.trim()
+                    ))
+                })?;
+            keys.insert(action, line_keys);
+        }
+
+        let mut bindings = HashMap::new();
+        // Visit the actions in a fixed order, so a conflict is always reported the same way
+        for (action, _, _) in ACTIONS.iter() {
+            for key in &keys[action] {
+                if let Some(other) = bindings.insert((action.context(), *key), *action) {
+                    if other != *action {
+                        return Err(Error::Keymap(format!(
+                            "`{}` and `{}` are both bound to `{}`",
+                            other.name(),
+                            action.name(),
+                            key_name(*key)
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    /// Returns the action bound to `key`, preferring the bindings of the inspect overlay and
+    /// the connections panel while they are open.
+    pub fn action(
+        &self,
+        key: KeyCode,
+        inspect_view: bool,
+        connections_view: bool,
+    ) -> Option<Action> {
+        [
+            (inspect_view, Context::Inspect),
+            (connections_view, Context::Connections),
+            (true, Context::Global),
+        ]
+        .into_iter()
+        .filter(|(open, _)| *open)
+        .find_map(|(_, context)| self.bindings.get(&(context, key)).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(contents: &str) -> String {
+        match Keymap::parse(contents) {
+            Ok(_) => panic!("`{}` was accepted", contents),
+            Err(Error::Keymap(message)) => message,
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    fn global(keymap: &Keymap, key: KeyCode) -> Option<Action> {
+        keymap.action(key, false, false)
+    }
+
+    #[test]
+    fn parse_keeps_the_defaults_without_bindings() {
+        let keymap = Keymap::parse("# no changes\n\n").unwrap();
+        assert_eq!(global(&keymap, KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(global(&keymap, KeyCode::Char(' ')), Some(Action::Select));
+        assert_eq!(global(&keymap, KeyCode::Char('j')), Some(Action::NavDown));
+    }
+
+    #[test]
+    fn parse_replaces_the_default_keys_of_the_actions_listed() {
+        let keymap = Keymap::parse("quit = x\nnav_down = down, J").unwrap();
+        assert_eq!(global(&keymap, KeyCode::Char('x')), Some(Action::Quit));
+        assert_eq!(global(&keymap, KeyCode::Char('q')), None);
+        assert_eq!(global(&keymap, KeyCode::Down), Some(Action::NavDown));
+        assert_eq!(global(&keymap, KeyCode::Char('J')), Some(Action::NavDown));
+        assert_eq!(global(&keymap, KeyCode::Char('j')), None);
+        // Others keep their defaults
+        assert_eq!(global(&keymap, KeyCode::Char('s')), Some(Action::Pause));
+    }
+
+    #[test]
+    fn parse_reads_named_keys() {
+        let keymap = Keymap::parse("select = comma\nquit = Esc\npause = space").unwrap();
+        assert_eq!(global(&keymap, KeyCode::Char(',')), Some(Action::Select));
+        assert_eq!(global(&keymap, KeyCode::Esc), Some(Action::Quit));
+        assert_eq!(global(&keymap, KeyCode::Char(' ')), Some(Action::Pause));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_actions_and_keys() {
+        assert_eq!(parse_error("jump = j"), "unknown action `jump`");
+        assert_eq!(
+            parse_error("quit = ctrl-q"),
+            "unknown key for `quit`: ctrl-q"
+        );
+        assert_eq!(parse_error("quit = q,"), "unknown key for `quit`: q,");
+        assert_eq!(parse_error("quit q"), "expected `action = key`: quit q");
+    }
+
+    #[test]
+    fn parse_rejects_a_key_bound_to_two_global_actions() {
+        assert_eq!(
+            parse_error("pause = q"),
+            "`quit` and `pause` are both bound to `q`"
+        );
+        assert_eq!(
+            parse_error("quit = space"),
+            "`quit` and `select` are both bound to `space`"
+        );
+    }
+
+    #[test]
+    fn parse_rejects_a_key_bound_to_two_inspect_actions() {
+        assert_eq!(
+            parse_error("notify = w"),
+            "`write` and `notify` are both bound to `w`"
+        );
+    }
+
+    #[test]
+    fn parse_lets_an_inspect_key_shadow_a_global_one() {
+        let keymap = Keymap::parse("notify = f").unwrap();
+        assert_eq!(
+            keymap.action(KeyCode::Char('f'), true, false),
+            Some(Action::Notify)
+        );
+        assert_eq!(global(&keymap, KeyCode::Char('f')), Some(Action::Favorite));
+        assert_eq!(keymap.action(KeyCode::Char('n'), true, false), None);
+    }
+}
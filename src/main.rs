@@ -1,8 +1,9 @@
 #[macro_use]
 extern crate lazy_static;
 use crate::viewer::viewer;
+use clap::Parser;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -10,8 +11,15 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 use std::{error::Error, io};
 
 mod app;
+mod cli;
 mod company_codes;
+mod config;
+mod discovery_log;
+mod error;
+mod gatt_names;
+mod keymap;
 mod scan;
+mod status_server;
 mod structs;
 mod utils;
 mod viewer;
@@ -19,21 +27,54 @@ mod widgets;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    let args = cli::Args::parse();
+
+    if args.list_adapters {
+        let adapters = match scan::list_adapters().await {
+            Ok(adapters) => adapters,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        if adapters.is_empty() {
+            eprintln!("No Bluetooth adapters found. Check that Bluetooth is turned on and available to this user.");
+            std::process::exit(1);
+        }
+        for (index, adapter) in adapters.iter().enumerate() {
+            let marker = if index == 0 {
+                " (used for scanning)"
+            } else {
+                ""
+            };
+            println!("{}: {}{}", index, adapter, marker);
+        }
+        return Ok(());
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Without the alternate screen the UI draws over the terminal's content, which is left in
+    // place afterwards with anything else written to it.
+    if !args.no_alt_screen {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    execute!(stdout, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = app::App::new();
+    let mut app = app::App::new(&args);
     app.scan().await;
     viewer(&mut terminal, &mut app).await?;
 
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
+    if !args.no_alt_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    }
     Ok(())
 }
@@ -1,28 +1,150 @@
 use crate::app::DeviceData;
-use crate::structs::{Characteristic, DeviceInfo};
+use crate::error::Error;
+use crate::structs::{Characteristic, DeviceInfo, NUS_RX_UUID, NUS_TX_UUID};
+use crate::utils::normalize_uuid;
 use btleplug::api::{
-    Central, CentralEvent, Manager as _, Peripheral, PeripheralProperties, ScanFilter,
+    BDAddr, Central, CentralEvent, CharPropFlags, Manager as _, Peripheral, PeripheralProperties,
+    ScanFilter, WriteType,
 };
-use btleplug::platform::Manager;
+use btleplug::platform::{Adapter, Manager};
 use futures::StreamExt;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    oneshot,
+};
 use tokio::time::timeout;
+use uuid::Uuid;
+
+/// Builds a `DeviceInfo` from a peripheral and its advertised properties.
+fn device_info(
+    device: &btleplug::platform::Peripheral,
+    properties: PeripheralProperties,
+) -> DeviceInfo {
+    DeviceInfo::new(
+        device.id().to_string(),
+        properties.local_name.clone(),
+        properties.tx_power_level,
+        properties.address.to_string(),
+        properties.rssi,
+        properties.manufacturer_data.clone(),
+        properties.services.clone(),
+        properties.service_data.clone(),
+        Some(device.clone()),
+        properties,
+    )
+}
+
+/// Returns the platform's description of each Bluetooth adapter, in the order they are
+/// enumerated. Scanning uses the first one.
+pub async fn list_adapters() -> Result<Vec<String>, Error> {
+    let manager = Manager::new().await.map_err(Error::scan)?;
+    let mut adapters = Vec::new();
+    for adapter in manager.adapters().await.map_err(Error::scan)? {
+        adapters.push(adapter.adapter_info().await.map_err(Error::scan)?);
+    }
+    Ok(adapters)
+}
+
+/// Finds a peripheral already discovered by the adapter by its Bluetooth address
+/// (`aa:bb:cc:dd:ee:ff`) or, on macOS, its UUID. The device does not need to be in the
+/// device table, but the OS must have seen it.
+pub async fn find_peripheral(address: &str) -> Result<DeviceInfo, Error> {
+    let address = address.trim();
+    let bd_addr = BDAddr::from_str(address).ok();
+    let uuid = normalize_uuid(address).ok();
+    if bd_addr.is_none() && uuid.is_none() {
+        return Err(Error::InvalidAddress(address.to_string()));
+    }
+
+    let manager = Manager::new().await?;
+    let adapters = manager.adapters().await?;
+    let central = adapters.into_iter().next().ok_or(Error::AdapterNotFound)?;
+    let peripherals = central.peripherals().await?;
+
+    for device in peripherals {
+        let matches_address = bd_addr == Some(device.address());
+        let matches_uuid = uuid.is_some_and(|uuid| device.id().to_string() == uuid.to_string());
+        if matches_address || matches_uuid {
+            let properties = device.properties().await?.unwrap_or_default();
+            return Ok(device_info(&device, properties));
+        }
+    }
+    Err(Error::DeviceNotFound(address.to_string()))
+}
 
 /// Scans for Bluetooth devices and sends the information to the provided `mpsc::Sender`.
 /// The scan can be paused by setting the `pause_signal` to `true`.
-pub async fn bluetooth_scan(tx: mpsc::UnboundedSender<DeviceData>, pause_signal: Arc<AtomicBool>) {
-    let manager = Manager::new().await.unwrap();
-    let adapters = manager.adapters().await.unwrap();
-    let central = adapters.into_iter().next().expect("No adapters found");
+/// Advertisements are sent with `try_send` so a flood of them can never block the scan; when
+/// the channel is full the advertisement is dropped and counted in `dropped_events`.
+/// Only devices advertising one of `services` are scanned for, unless it is empty.
+pub async fn bluetooth_scan(
+    tx: mpsc::Sender<DeviceData>,
+    pause_signal: Arc<AtomicBool>,
+    dropped_events: Arc<AtomicUsize>,
+    adapter_timeout: Duration,
+    services: Vec<Uuid>,
+) {
+    if let Err(e) = scan_events(&tx, pause_signal, dropped_events, adapter_timeout, services).await
+    {
+        let _ = tx.send(DeviceData::Error(e)).await;
+    }
+}
+
+/// Returns the first adapter, polling for up to `timeout` when there is none yet, e.g. while a
+/// USB dongle is still being set up.
+async fn wait_for_adapter(
+    tx: &mpsc::Sender<DeviceData>,
+    manager: &Manager,
+    timeout: Duration,
+) -> Result<Adapter, Error> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+    let started = tokio::time::Instant::now();
+    let mut waiting = false;
+    loop {
+        let adapters = manager.adapters().await.map_err(Error::scan)?;
+        if let Some(central) = adapters.into_iter().next() {
+            return Ok(central);
+        }
+        if started.elapsed() >= timeout {
+            return Err(Error::AdapterNotFound);
+        }
+        if !waiting {
+            waiting = true;
+            let _ = tx
+                .send(DeviceData::Status(
+                    "Waiting for Bluetooth adapter…".to_owned(),
+                ))
+                .await;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Runs the scan for `bluetooth_scan`, returning the error that stopped it, if any.
+async fn scan_events(
+    tx: &mpsc::Sender<DeviceData>,
+    pause_signal: Arc<AtomicBool>,
+    dropped_events: Arc<AtomicUsize>,
+    adapter_timeout: Duration,
+    services: Vec<Uuid>,
+) -> Result<(), Error> {
+    let manager = Manager::new().await.map_err(Error::scan)?;
+    let central = wait_for_adapter(tx, &manager, adapter_timeout).await?;
+
+    // Don't touch the radio until scanning is unpaused, e.g. when started with `--start-paused`
+    while pause_signal.load(Ordering::SeqCst) {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
 
     central
-        .start_scan(ScanFilter::default())
+        .start_scan(ScanFilter { services })
         .await
-        .expect("Scanning failure");
-    let mut events = central.events().await.unwrap();
+        .map_err(Error::scan)?;
+    let mut events = central.events().await.map_err(Error::scan)?;
 
     while let Some(event) = events.next().await {
         // Check the pause signal before processing the event
@@ -30,45 +152,123 @@ pub async fn bluetooth_scan(tx: mpsc::UnboundedSender<DeviceData>, pause_signal:
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         }
 
-        if let CentralEvent::DeviceDiscovered(id) = event {
+        if let CentralEvent::DeviceDiscovered(id) | CentralEvent::DeviceUpdated(id) = event {
             if let Ok(device) = central.peripheral(&id).await {
-                let properties = device
-                    .properties()
-                    .await
-                    .unwrap()
-                    .unwrap_or(PeripheralProperties::default());
-
-                // Add the new device's information to the accumulated list
-                let device = DeviceInfo::new(
-                    device.id().to_string(),
-                    properties.local_name,
-                    properties.tx_power_level,
-                    properties.address.to_string(),
-                    properties.rssi,
-                    properties.manufacturer_data,
-                    properties.services,
-                    properties.service_data,
-                    device.clone(),
-                );
+                // Skip devices whose properties can't be read, e.g. when they just went away
+                let Ok(properties) = device.properties().await else {
+                    continue;
+                };
+                let properties = properties.unwrap_or_default();
+
+                // Send discovered devices and updates alike, the UI updates known devices in place
+                let device = device_info(&device, properties);
 
                 // Send a clone of the accumulated device information so far
-                let _ = tx.send(DeviceData::DeviceInfo(device));
+                if let Err(TrySendError::Full(_)) =
+                    tx.try_send(DeviceData::DeviceInfo(Box::new(device)))
+                {
+                    dropped_events.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Connects to a device unless it is already connected, so an existing connection is reused.
+/// Some backends fail with an "already connected" error instead, which is treated as success.
+async fn connect(device: &btleplug::platform::Peripheral) -> btleplug::Result<()> {
+    if device.is_connected().await.unwrap_or(false) {
+        return Ok(());
+    }
+    match device.connect().await {
+        Err(_) if device.is_connected().await.unwrap_or(false) => Ok(()),
+        result => result,
+    }
+}
+
+/// Feeds synthetic devices with fluctuating RSSI instead of scanning, so the UI can be tried
+/// out without Bluetooth hardware. Behaves like `bluetooth_scan` towards the pause signal and
+/// the channel.
+pub async fn synthetic_scan(
+    tx: mpsc::Sender<DeviceData>,
+    pause_signal: Arc<AtomicBool>,
+    dropped_events: Arc<AtomicUsize>,
+) {
+    const DEVICES: u8 = 12;
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+    let mut tick: u32 = 0;
+
+    loop {
+        interval.tick().await;
+        while pause_signal.load(Ordering::SeqCst) {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+
+        let i = (tick % DEVICES as u32) as u8;
+        let mut properties = PeripheralProperties {
+            address: BDAddr::from([0x02, 0x00, 0x00, 0x00, 0x00, i]),
+            local_name: (i % 3 != 2).then(|| format!("Synthetic {}", i)),
+            tx_power_level: i.is_multiple_of(2).then_some(-59),
+            rssi: Some(-40 - ((tick * 7 + i as u32 * 13) % 50) as i16),
+            ..PeripheralProperties::default()
+        };
+        match i % 4 {
+            // Apple manufacturer data
+            0 => {
+                properties
+                    .manufacturer_data
+                    .insert(0x004C, vec![0x02, 0x15, i, 0xAB]);
+            }
+            // Battery Service data
+            1 => {
+                let battery = Uuid::from_u128(0x0000180F_0000_1000_8000_00805F9B34FB);
+                properties.services.push(battery);
+                properties.service_data.insert(battery, vec![100 - i * 5]);
+            }
+            // Eddystone-URL frame for https://example.com
+            2 => {
+                let eddystone = Uuid::from_u128(0x0000FEAA_0000_1000_8000_00805F9B34FB);
+                properties.service_data.insert(
+                    eddystone,
+                    [&[0x10, 0xEE, 0x03][..], b"example", &[0x07]].concat(),
+                );
             }
+            _ => {}
+        }
+
+        let device = DeviceInfo::new_synthetic(properties);
+        if let Err(TrySendError::Full(_)) = tx.try_send(DeviceData::DeviceInfo(Box::new(device))) {
+            dropped_events.fetch_add(1, Ordering::SeqCst);
         }
+        tick = tick.wrapping_add(1);
     }
 }
 
 /// Gets the characteristics of a Bluetooth device and returns them as a `Vec<Characteristic>`.
-/// The device is identified by its address or UUID.
+/// The device is identified by its address or UUID. The connection is left open for the
+/// reads, writes and subscriptions that follow, and is reused if it is already open.
+/// Connecting gives up after `duration`. The outcome is sent tagged with `attempt`, the id of
+/// the connection attempt.
 pub async fn get_characteristics(
-    tx: mpsc::UnboundedSender<DeviceData>,
+    tx: mpsc::Sender<DeviceData>,
     peripheral: Arc<DeviceInfo>,
+    duration: Duration,
+    attempt: u64,
 ) {
-    let duration = Duration::from_secs(10);
     match &peripheral.device {
-        Some(device) => match timeout(duration, device.connect()).await {
+        Some(device) => match timeout(duration, connect(device)).await {
             Ok(Ok(_)) => {
                 if let Some(device) = &peripheral.device {
+                    // Services are only discovered once per connection
+                    if device.characteristics().is_empty() {
+                        if let Err(e) = device.discover_services().await {
+                            let _ = tx
+                                .send(DeviceData::ConnectError(attempt, Error::Discovery(e)))
+                                .await;
+                            return;
+                        }
+                    }
                     let characteristics = device.characteristics();
                     let mut result = Vec::new();
                     for characteristic in characteristics {
@@ -83,21 +283,262 @@ pub async fn get_characteristics(
                             service: characteristic.service_uuid,
                         });
                     }
-                    let _ = tx.send(DeviceData::Characteristics(result));
+                    let _ = tx.send(DeviceData::Characteristics(attempt, result)).await;
                 }
             }
             Ok(Err(e)) => {
-                tx.send(DeviceData::Error(format!("Connection error: {}", e)))
-                    .unwrap();
+                let _ = tx
+                    .send(DeviceData::ConnectError(attempt, Error::Connect(e)))
+                    .await;
             }
             Err(_) => {
-                tx.send(DeviceData::Error("Connection timed out".to_string()))
-                    .unwrap();
+                let _ = tx
+                    .send(DeviceData::ConnectError(attempt, Error::ConnectTimeout))
+                    .await;
             }
         },
         None => {
-            tx.send(DeviceData::Error("Device not found".to_string()))
-                .unwrap();
+            let _ = tx
+                .send(DeviceData::ConnectError(attempt, Error::NoPeripheral))
+                .await;
+        }
+    }
+}
+
+/// Disconnects from a device. The connection watcher reports the disconnect once it happens.
+pub async fn disconnect(tx: mpsc::Sender<DeviceData>, peripheral: Arc<DeviceInfo>) {
+    if let Some(device) = &peripheral.device {
+        if let Err(e) = device.disconnect().await {
+            let _ = tx.send(DeviceData::Error(Error::Disconnect(e))).await;
+        }
+    }
+}
+
+/// Disconnects from a device whose connection attempt was cancelled, in case the platform went
+/// on to connect it. Errors are ignored, since the device is usually not connected.
+pub async fn abandon_connection(peripheral: Arc<DeviceInfo>) {
+    if let Some(device) = &peripheral.device {
+        let _ = device.disconnect().await;
+    }
+}
+
+/// Unsubscribes from the notifications or indications of a characteristic of a device.
+pub async fn unsubscribe(tx: mpsc::Sender<DeviceData>, peripheral: Arc<DeviceInfo>, uuid: Uuid) {
+    if let Some((device, characteristic)) = find_characteristic(&tx, &peripheral, uuid).await {
+        if let Err(e) = device.unsubscribe(&characteristic).await {
+            let _ = tx.send(DeviceData::Error(Error::Subscribe(e))).await;
         }
     }
 }
+
+/// Watches a connected device and sends `DeviceData::Disconnected` once the connection drops.
+pub async fn watch_connection(tx: mpsc::Sender<DeviceData>, peripheral: Arc<DeviceInfo>) {
+    if let Some(device) = &peripheral.device {
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            if !device.is_connected().await.unwrap_or(false) {
+                let _ = tx
+                    .send(DeviceData::Disconnected(peripheral.id.clone()))
+                    .await;
+                break;
+            }
+        }
+    }
+}
+
+/// Finds a characteristic of a connected device, sending an error when it isn't there.
+async fn find_characteristic(
+    tx: &mpsc::Sender<DeviceData>,
+    peripheral: &DeviceInfo,
+    uuid: Uuid,
+) -> Option<(
+    btleplug::platform::Peripheral,
+    btleplug::api::Characteristic,
+)> {
+    let found = peripheral.device.as_ref().and_then(|device| {
+        device
+            .characteristics()
+            .into_iter()
+            .find(|c| c.uuid == uuid)
+            .map(|characteristic| (device.clone(), characteristic))
+    });
+    if found.is_none() {
+        let _ = tx
+            .send(DeviceData::Error(Error::CharacteristicNotFound(uuid)))
+            .await;
+    }
+    found
+}
+
+/// Reads the value of a characteristic and sends it as `DeviceData::CharacteristicValue`.
+pub async fn read_characteristic(
+    tx: mpsc::Sender<DeviceData>,
+    peripheral: Arc<DeviceInfo>,
+    uuid: Uuid,
+) {
+    if let Some((device, characteristic)) = find_characteristic(&tx, &peripheral, uuid).await {
+        let data = match device.read(&characteristic).await {
+            Ok(value) => DeviceData::CharacteristicValue(uuid, value),
+            Err(e) => DeviceData::Error(Error::Read(e)),
+        };
+        let _ = tx.send(data).await;
+    }
+}
+
+/// Reads each of `uuids` in turn and sends the values as `DeviceData::CharacteristicValue`.
+/// This is best effort: characteristics that are missing or fail to read are skipped silently.
+pub async fn read_characteristics(
+    tx: mpsc::Sender<DeviceData>,
+    peripheral: Arc<DeviceInfo>,
+    uuids: Vec<Uuid>,
+) {
+    let Some(device) = &peripheral.device else {
+        return;
+    };
+    let characteristics = device.characteristics();
+    for uuid in uuids {
+        let Some(characteristic) = characteristics.iter().find(|c| c.uuid == uuid) else {
+            continue;
+        };
+        if let Ok(value) = device.read(characteristic).await {
+            let _ = tx.send(DeviceData::CharacteristicValue(uuid, value)).await;
+        }
+    }
+}
+
+/// Writes a value to a characteristic with the given `write_type`, and sends
+/// `DeviceData::CharacteristicWritten` once written. With `verify` the value is read
+/// back afterwards and sent as `DeviceData::WriteVerified` to compare against what was written.
+pub async fn write_characteristic(
+    tx: mpsc::Sender<DeviceData>,
+    peripheral: Arc<DeviceInfo>,
+    uuid: Uuid,
+    value: Vec<u8>,
+    write_type: WriteType,
+    verify: bool,
+) {
+    if let Some((device, characteristic)) = find_characteristic(&tx, &peripheral, uuid).await {
+        if let Err(e) = device.write(&characteristic, &value, write_type).await {
+            let _ = tx.send(DeviceData::Error(Error::WriteRejected(e))).await;
+            return;
+        }
+        let _ = tx
+            .send(DeviceData::CharacteristicWritten(uuid, value.clone()))
+            .await;
+
+        if verify {
+            let data = match device.read(&characteristic).await {
+                Ok(read) => DeviceData::WriteVerified(uuid, value, read),
+                Err(e) => DeviceData::Error(Error::Read(e)),
+            };
+            let _ = tx.send(data).await;
+        }
+    }
+}
+
+/// Subscribes to a characteristic and forwards every notification or indication as
+/// `DeviceData::CharacteristicValue`. The subscription ends when `stop` is sent or dropped.
+pub async fn subscribe_characteristic(
+    tx: mpsc::Sender<DeviceData>,
+    peripheral: Arc<DeviceInfo>,
+    uuid: Uuid,
+    mut stop: oneshot::Receiver<()>,
+) {
+    let Some((device, characteristic)) = find_characteristic(&tx, &peripheral, uuid).await else {
+        return;
+    };
+    let mut notifications = match device.notifications().await {
+        Ok(notifications) => notifications,
+        Err(e) => {
+            let _ = tx.send(DeviceData::Error(Error::Notification(e))).await;
+            return;
+        }
+    };
+    if let Err(e) = device.subscribe(&characteristic).await {
+        let _ = tx.send(DeviceData::Error(Error::Subscribe(e))).await;
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            Some(notification) = notifications.next() => {
+                if notification.uuid == uuid {
+                    let _ = tx.send(DeviceData::CharacteristicValue(uuid, notification.value)).await;
+                }
+            }
+            _ = &mut stop => break,
+        }
+    }
+
+    let _ = device.unsubscribe(&characteristic).await;
+}
+
+/// Opens a Nordic UART Service console on a connected device. Notifications from the TX
+/// characteristic are forwarded as `DeviceData::NusData`, and every line received on `input`
+/// is written to the RX characteristic. The console ends when `input` is closed.
+pub async fn nus_terminal(
+    tx: mpsc::Sender<DeviceData>,
+    peripheral: Arc<DeviceInfo>,
+    mut input: mpsc::UnboundedReceiver<String>,
+) {
+    let device = match &peripheral.device {
+        Some(device) => device,
+        None => {
+            let _ = tx.send(DeviceData::Error(Error::NoPeripheral)).await;
+            return;
+        }
+    };
+
+    let characteristics = device.characteristics();
+    let rx_char = characteristics.iter().find(|c| c.uuid == NUS_RX_UUID);
+    let tx_char = characteristics.iter().find(|c| c.uuid == NUS_TX_UUID);
+    let (rx_char, tx_char) = match (rx_char, tx_char) {
+        (Some(rx_char), Some(tx_char)) => (rx_char, tx_char),
+        _ => {
+            let _ = tx.send(DeviceData::Error(Error::NusNotFound)).await;
+            return;
+        }
+    };
+
+    let mut notifications = match device.notifications().await {
+        Ok(notifications) => notifications,
+        Err(e) => {
+            let _ = tx.send(DeviceData::Error(Error::Notification(e))).await;
+            return;
+        }
+    };
+    if let Err(e) = device.subscribe(tx_char).await {
+        let _ = tx.send(DeviceData::Error(Error::Subscribe(e))).await;
+        return;
+    }
+
+    let write_type = if rx_char
+        .properties
+        .contains(CharPropFlags::WRITE_WITHOUT_RESPONSE)
+    {
+        WriteType::WithoutResponse
+    } else {
+        WriteType::WithResponse
+    };
+
+    loop {
+        tokio::select! {
+            Some(notification) = notifications.next() => {
+                if notification.uuid == NUS_TX_UUID {
+                    let _ = tx.send(DeviceData::NusData(notification.value)).await;
+                }
+            }
+            line = input.recv() => match line {
+                Some(line) => {
+                    let data = format!("{}\n", line).into_bytes();
+                    if let Err(e) = device.write(rx_char, &data, write_type).await {
+                        let _ = tx.send(DeviceData::Error(Error::WriteRejected(e))).await;
+                    }
+                }
+                None => break,
+            },
+        }
+    }
+
+    let _ = device.unsubscribe(tx_char).await;
+}
@@ -0,0 +1,93 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, watch},
+};
+
+use crate::{app::DeviceData, error::Error, structs::StatusSnapshot};
+
+/// The largest request read before answering, which is plenty for a request line and headers.
+const MAX_REQUEST_BYTES: usize = 8192;
+/// How long a client has to send its request before it's answered with a 408 and dropped, so
+/// idle connections don't pile up.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves the latest snapshot from `snapshot` as JSON on localhost: the devices at `/devices`
+/// and the scanner state at `/status`. Each connection is answered on its own task from the
+/// snapshot alone, so a slow client never holds up the UI. Binding failures are reported
+/// through `tx`.
+pub async fn status_server(
+    port: u16,
+    snapshot: watch::Receiver<StatusSnapshot>,
+    tx: mpsc::Sender<DeviceData>,
+) {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            let _ = tx.send(DeviceData::Error(Error::Serve(e))).await;
+            return;
+        }
+    };
+
+    while let Ok((stream, _)) = listener.accept().await {
+        let snapshot = snapshot.clone();
+        tokio::spawn(async move { respond(stream, snapshot).await });
+    }
+}
+
+/// Answers a single request with the JSON for its path, a 404 for other paths, or a 408 when
+/// the request doesn't arrive within `REQUEST_TIMEOUT`.
+async fn respond(mut stream: TcpStream, snapshot: watch::Receiver<StatusSnapshot>) {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    let read = async {
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            match stream.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => request.extend_from_slice(&buffer[..n]),
+            }
+            if request.len() > MAX_REQUEST_BYTES {
+                break;
+            }
+        }
+    };
+    if tokio::time::timeout(REQUEST_TIMEOUT, read).await.is_err() {
+        let response =
+            "HTTP/1.1 408 Request Timeout\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+        return;
+    }
+
+    // e.g. `GET /devices HTTP/1.1`
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+
+    let body = {
+        let snapshot = snapshot.borrow();
+        match (method, path) {
+            ("GET", "/devices") => serde_json::to_string(&snapshot.devices).ok(),
+            ("GET", "/status") => serde_json::to_string(&snapshot.status).ok(),
+            _ => None,
+        }
+    };
+    let response = match body {
+        Some(body) => format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ),
+        None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            .to_owned(),
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
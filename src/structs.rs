@@ -1,8 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{hash_map::RandomState, HashMap, HashSet};
 
-use btleplug::api::CharPropFlags;
+use btleplug::api::{CharPropFlags, PeripheralProperties, WriteType};
 use uuid::Uuid;
 
+use crate::{
+    cli::HexFormat,
+    utils::{anonymize_address, classify_device, extract_manufacturer_data, format_hex},
+};
+
+/// The Nordic UART Service, commonly used for debug consoles.
+pub const NUS_SERVICE_UUID: Uuid = Uuid::from_u128(0x6E400001_B5A3_F393_E0A9_E50E24DCCA9E);
+/// The NUS characteristic that receives data written by the central.
+pub const NUS_RX_UUID: Uuid = Uuid::from_u128(0x6E400002_B5A3_F393_E0A9_E50E24DCCA9E);
+/// The NUS characteristic that notifies the central with data from the device.
+pub const NUS_TX_UUID: Uuid = Uuid::from_u128(0x6E400003_B5A3_F393_E0A9_E50E24DCCA9E);
+/// The Battery Level characteristic, a single byte percentage.
+pub const BATTERY_LEVEL_UUID: Uuid = Uuid::from_u128(0x00002A19_0000_1000_8000_00805F9B34FB);
+/// The TX Power Level characteristic, the transmit power in dBm as a signed byte.
+pub const TX_POWER_LEVEL_UUID: Uuid = Uuid::from_u128(0x00002A07_0000_1000_8000_00805F9B34FB);
+
 /// A struct to hold the information of a Bluetooth device.
 #[derive(Clone, Default)]
 #[allow(dead_code)]
@@ -15,13 +31,20 @@ pub struct DeviceInfo {
     pub manufacturer_data: HashMap<u16, Vec<u8>>,
     pub services: Vec<Uuid>,
     pub detected_at: String,
+    /// How many advertisements of the device have been received.
+    pub times_seen: u32,
+    /// The exponential moving average of the RSSI, when `--rssi-smoothing` is set.
+    pub smoothed_rssi: Option<f32>,
 
     pub service_data: HashMap<Uuid, Vec<u8>>,
     pub device: Option<btleplug::platform::Peripheral>,
+    pub properties: PeripheralProperties,
 }
 
 impl DeviceInfo {
     /// Creates a new `DeviceInfo` with the provided information.
+    /// `name` is the local name reported by btleplug, which doesn't tell the complete name from
+    /// a shortened one; devices without a name are "Unknown".
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: String,
@@ -32,7 +55,8 @@ impl DeviceInfo {
         manufacturer_data: HashMap<u16, Vec<u8>>,
         services: Vec<Uuid>,
         service_data: HashMap<Uuid, Vec<u8>>,
-        device: btleplug::platform::Peripheral,
+        device: Option<btleplug::platform::Peripheral>,
+        properties: PeripheralProperties,
     ) -> Self {
         Self {
             id,
@@ -43,13 +67,53 @@ impl DeviceInfo {
             manufacturer_data,
             services,
             detected_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            times_seen: 1,
+            smoothed_rssi: None,
             service_data,
-            device: Some(device),
+            device,
+            properties,
+        }
+    }
+
+    /// Creates a `DeviceInfo` from advertised properties alone, without a peripheral behind it,
+    /// e.g. for the devices fed by `--demo`. Its id is its address.
+    pub fn new_synthetic(properties: PeripheralProperties) -> Self {
+        Self::new(
+            properties.address.to_string(),
+            properties.local_name.clone(),
+            properties.tx_power_level,
+            properties.address.to_string(),
+            properties.rssi,
+            properties.manufacturer_data.clone(),
+            properties.services.clone(),
+            properties.service_data.clone(),
+            None,
+            properties,
+        )
+    }
+
+    /// Returns a copy of the device with its id and address redacted by `anonymize_address`
+    /// with the session's `key`.
+    pub fn anonymized(&self, key: &RandomState) -> Self {
+        Self {
+            id: anonymize_address(&self.id, key),
+            address: anonymize_address(&self.address, key),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the RSSI shown in the device table: the smoothed RSSI when smoothing, or else the
+    /// last one advertised.
+    pub fn display_rssi(&self) -> String {
+        match self.smoothed_rssi {
+            Some(rssi) => format!("{:.0}", rssi),
+            None => self.rssi.clone(),
         }
     }
 
+    /// Returns how the platform identifies the device: CoreBluetooth hides addresses behind a
+    /// per-host UUID on macOS, while other platforms use the address.
     pub fn get_id(&self) -> String {
-        // Returns the `uuid` or `address` of the device if MacOS or Linux.
         if cfg!(target_os = "macos") {
             self.id.clone()
         } else {
@@ -66,12 +130,324 @@ pub struct Characteristic {
     pub service: Uuid,
 }
 
+impl Characteristic {
+    /// Returns how the characteristic is written: with response by default, or without when
+    /// `without_response` is preferred. A characteristic supporting only one of them is locked
+    /// to it, and one that isn't writable gives `None`.
+    pub fn write_type(&self, without_response: bool) -> Option<WriteType> {
+        let with = self.properties.contains(CharPropFlags::WRITE);
+        let without = self
+            .properties
+            .contains(CharPropFlags::WRITE_WITHOUT_RESPONSE);
+        match (with, without) {
+            (true, true) if without_response => Some(WriteType::WithoutResponse),
+            (true, _) => Some(WriteType::WithResponse),
+            (false, true) => Some(WriteType::WithoutResponse),
+            (false, false) => None,
+        }
+    }
+
+    /// Returns whether the characteristic can be written both with and without response.
+    pub fn has_write_choice(&self) -> bool {
+        self.properties
+            .contains(CharPropFlags::WRITE | CharPropFlags::WRITE_WITHOUT_RESPONSE)
+    }
+
+    /// Returns how a subscription to the characteristic is labelled, or `None` when it supports
+    /// neither notifications nor indications. Notify is preferred when both are supported.
+    pub fn subscription_label(&self) -> Option<&'static str> {
+        if self.properties.contains(CharPropFlags::NOTIFY) {
+            Some("Subscribed ●")
+        } else if self.properties.contains(CharPropFlags::INDICATE) {
+            Some("Indicating ●")
+        } else {
+            None
+        }
+    }
+}
+
+/// Which characteristics the inspect overlay shows, by their properties.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PropertyFilter {
+    All,
+    Read,
+    Write,
+    Notify,
+}
+
+impl PropertyFilter {
+    /// Returns the next filter in the order the filters are cycled through.
+    pub fn next(self) -> Self {
+        match self {
+            PropertyFilter::All => PropertyFilter::Read,
+            PropertyFilter::Read => PropertyFilter::Write,
+            PropertyFilter::Write => PropertyFilter::Notify,
+            PropertyFilter::Notify => PropertyFilter::All,
+        }
+    }
+
+    /// Returns whether a characteristic with the given properties passes the filter.
+    /// Writes with or without response count as writable, and notify or indicate as notifying.
+    pub fn matches(&self, properties: CharPropFlags) -> bool {
+        match self {
+            PropertyFilter::All => true,
+            PropertyFilter::Read => properties.contains(CharPropFlags::READ),
+            PropertyFilter::Write => {
+                properties.intersects(CharPropFlags::WRITE | CharPropFlags::WRITE_WITHOUT_RESPONSE)
+            }
+            PropertyFilter::Notify => {
+                properties.intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE)
+            }
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PropertyFilter::All => "all",
+            PropertyFilter::Read => "read",
+            PropertyFilter::Write => "write",
+            PropertyFilter::Notify => "notify",
+        }
+    }
+}
+
+/// A condition a device must meet to be shown in the device table. Active filters combine, so
+/// only devices meeting all of them are shown.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DeviceFilter {
+    ManufacturerData,
+    Services,
+}
+
+impl DeviceFilter {
+    pub fn matches(&self, device: &DeviceInfo) -> bool {
+        match self {
+            DeviceFilter::ManufacturerData => !device.manufacturer_data.is_empty(),
+            DeviceFilter::Services => !device.services.is_empty(),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeviceFilter::ManufacturerData => "manufacturer data",
+            DeviceFilter::Services => "services",
+        }
+    }
+}
+
 /// A struct to hold the information of a GATT Descriptor.
 pub struct ManufacturerData {
     pub company_code: String,
     pub data: String,
 }
 
+/// What kind of device a device is, as guessed by `classify_device`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DeviceCategory {
+    Phone,
+    Wearable,
+    Beacon,
+    Sensor,
+    Audio,
+    Unknown,
+}
+
+impl DeviceCategory {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeviceCategory::Phone => "Phone",
+            DeviceCategory::Wearable => "Wearable",
+            DeviceCategory::Beacon => "Beacon",
+            DeviceCategory::Sensor => "Sensor",
+            DeviceCategory::Audio => "Audio",
+            DeviceCategory::Unknown => "?",
+        }
+    }
+}
+
+/// A struct to hold the state of a connection to a peripheral.
+pub struct ConnectionState {
+    pub device: DeviceInfo,
+    pub connected_at: std::time::Instant,
+    /// The negotiated ATT MTU, when the platform exposes it.
+    pub mtu: Option<u16>,
+    /// The negotiated connection parameters, when the platform exposes them.
+    pub interval: Option<std::time::Duration>,
+    pub latency: Option<u16>,
+    pub supervision_timeout: Option<std::time::Duration>,
+    pub characteristics: usize,
+    pub subscriptions: HashSet<Uuid>,
+}
+
+/// A column of the device table.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    Identifier,
+    Name,
+    TxPower,
+    Rssi,
+    Company,
+    Services,
+    Distance,
+    Category,
+}
+
+/// The columns shown when none are configured.
+pub const DEFAULT_COLUMNS: [Column; 4] = [
+    Column::Identifier,
+    Column::Name,
+    Column::TxPower,
+    Column::Rssi,
+];
+
+impl Column {
+    /// Parses a column from its name in the configuration file, e.g. `tx_power`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "identifier" | "address" => Some(Column::Identifier),
+            "name" => Some(Column::Name),
+            "tx_power" => Some(Column::TxPower),
+            "rssi" => Some(Column::Rssi),
+            "company" => Some(Column::Company),
+            "services" => Some(Column::Services),
+            "distance" => Some(Column::Distance),
+            "category" | "type" => Some(Column::Category),
+            _ => None,
+        }
+    }
+
+    /// Returns the header of the column. The identifier is titled after what `get_id` returns
+    /// on the platform.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Identifier if cfg!(target_os = "macos") => "Identifier",
+            Column::Identifier => "Address",
+            Column::Name => "Name",
+            Column::TxPower => "TX Power",
+            Column::Rssi => "RSSI",
+            Column::Company => "Company",
+            Column::Services => "Services",
+            Column::Distance => "Distance",
+            Column::Category => "Type",
+        }
+    }
+
+    /// Formats the value of the column for a device. `distance` is the estimated distance.
+    pub fn value(&self, device: &DeviceInfo, distance: Option<f32>) -> String {
+        match self {
+            Column::Identifier => device.get_id(),
+            Column::Name => device.name.clone(),
+            Column::TxPower => device.tx_power.clone(),
+            Column::Rssi => device.display_rssi(),
+            Column::Company => extract_manufacturer_data(&device.manufacturer_data).company_code,
+            Column::Services => device.services.len().to_string(),
+            Column::Distance => distance.map_or_else(
+                || "n/a".to_owned(),
+                |distance| format!("~{:.1} m", distance),
+            ),
+            Column::Category => classify_device(device).label().to_owned(),
+        }
+    }
+
+    /// Returns whether the value of the column differs between two updates of a device.
+    /// Manufacturer data counts as a change of the company column even for the same company.
+    pub fn changed(&self, old: &DeviceInfo, new: &DeviceInfo) -> bool {
+        match self {
+            Column::Identifier => false,
+            Column::Name => old.name != new.name,
+            Column::TxPower => old.tx_power != new.tx_power,
+            Column::Rssi | Column::Distance => old.display_rssi() != new.display_rssi(),
+            Column::Company => old.manufacturer_data != new.manufacturer_data,
+            Column::Services => old.services != new.services,
+            Column::Category => classify_device(old) != classify_device(new),
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        match self {
+            Column::Identifier => 40,
+            Column::Name | Column::Company => 30,
+            Column::TxPower
+            | Column::Rssi
+            | Column::Services
+            | Column::Distance
+            | Column::Category => 10,
+        }
+    }
+}
+
+/// The kind of event recorded in the message log.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LogDirection {
+    Info,
+    Error,
+    /// A value written to a characteristic.
+    Sent,
+    /// A value read from, or notified by, a characteristic.
+    Received,
+}
+
+impl LogDirection {
+    /// Returns the label written before entries of this kind in the debug log.
+    pub fn label(self) -> &'static str {
+        match self {
+            LogDirection::Info => "INFO",
+            LogDirection::Error => "ERROR",
+            LogDirection::Sent => "SENT",
+            LogDirection::Received => "RECV",
+        }
+    }
+}
+
+/// A struct to hold an entry of the message log.
+pub struct LogEntry {
+    pub timestamp: String,
+    pub direction: LogDirection,
+    pub message: String,
+}
+
+/// Formats manufacturer data for a CSV cell, one `0x<company>: <hex>` entry per company in
+/// order of company identifier, separated by `; `, e.g. `0x004C: 02 15`.
+pub fn manufacturer_data_csv(data: &HashMap<u16, Vec<u8>>, hex_format: HexFormat) -> String {
+    let mut companies: Vec<_> = data.iter().collect();
+    companies.sort_by_key(|(code, _)| **code);
+    companies
+        .into_iter()
+        .map(|(code, bytes)| format!("0x{:04X}: {}", code, format_hex(bytes, hex_format)))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// A struct to hold a timestamped advertisement for a CSV log.
+#[derive(serde::Serialize)]
+pub struct AdvertisementCsv {
+    pub timestamp: String,
+    pub id: String,
+    pub name: String,
+    pub tx_power: String,
+    pub address: String,
+    pub rssi: String,
+    pub manufacturer_data: String,
+}
+
+impl AdvertisementCsv {
+    /// Creates the row for an advertisement of a device seen now, writing its bytes as hex in
+    /// `hex_format`.
+    pub fn new(device: &DeviceInfo, hex_format: HexFormat) -> Self {
+        Self {
+            timestamp: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            id: device.id.clone(),
+            name: device.name.clone(),
+            tx_power: device.tx_power.clone(),
+            address: device.address.clone(),
+            rssi: device.rssi.clone(),
+            manufacturer_data: manufacturer_data_csv(&device.manufacturer_data, hex_format),
+        }
+    }
+}
+
 /// A struct to hold data for a CSV file.
 #[derive(serde::Serialize)]
 pub struct DeviceCsv {
@@ -80,4 +456,169 @@ pub struct DeviceCsv {
     pub tx_power: String,
     pub address: String,
     pub rssi: String,
+    pub manufacturer_data: String,
+    pub note: String,
+}
+
+/// A device as served at `/devices` by `--serve`.
+#[derive(serde::Serialize)]
+pub struct DeviceJson {
+    pub id: String,
+    pub address: String,
+    pub name: String,
+    pub tx_power: Option<i16>,
+    pub rssi: Option<i16>,
+    pub detected_at: String,
+    pub times_seen: u32,
+    pub company: String,
+    pub category: String,
+    pub services: Vec<String>,
+    /// Hex manufacturer data by company identifier, e.g. `"0x004C": "02 15"`.
+    pub manufacturer_data: HashMap<String, String>,
+}
+
+impl DeviceJson {
+    /// Creates the JSON for a device, writing its bytes as hex in `hex_format`.
+    pub fn new(device: &DeviceInfo, hex_format: HexFormat) -> Self {
+        Self {
+            id: device.id.clone(),
+            address: device.address.clone(),
+            name: device.name.clone(),
+            tx_power: device.properties.tx_power_level,
+            rssi: device.properties.rssi,
+            detected_at: device.detected_at.clone(),
+            times_seen: device.times_seen,
+            company: extract_manufacturer_data(&device.manufacturer_data).company_code,
+            category: classify_device(device).label().to_owned(),
+            services: device.services.iter().map(Uuid::to_string).collect(),
+            manufacturer_data: device
+                .manufacturer_data
+                .iter()
+                .map(|(code, data)| (format!("0x{:04X}", code), format_hex(data, hex_format)))
+                .collect(),
+        }
+    }
+}
+
+/// The state of the scanner as served at `/status` by `--serve`.
+#[derive(serde::Serialize, Default)]
+pub struct StatusJson {
+    pub scanning: bool,
+    pub recording: bool,
+    pub devices: usize,
+    pub connections: usize,
+    pub dropped_events: usize,
+    pub evicted_devices: usize,
+    pub duplicate_discoveries: usize,
+}
+
+/// What `--serve` serves, published by the UI as it changes.
+#[derive(Default)]
+pub struct StatusSnapshot {
+    pub status: StatusJson,
+    pub devices: Vec<DeviceJson>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn characteristic(properties: CharPropFlags) -> Characteristic {
+        Characteristic {
+            uuid: NUS_RX_UUID,
+            properties,
+            descriptors: Vec::new(),
+            service: NUS_SERVICE_UUID,
+        }
+    }
+
+    #[test]
+    fn property_filter_all_matches_everything() {
+        assert!(PropertyFilter::All.matches(CharPropFlags::empty()));
+        assert!(PropertyFilter::All.matches(CharPropFlags::READ | CharPropFlags::NOTIFY));
+    }
+
+    #[test]
+    fn property_filter_read_matches_readable_characteristics() {
+        assert!(PropertyFilter::Read.matches(CharPropFlags::READ));
+        assert!(PropertyFilter::Read.matches(CharPropFlags::READ | CharPropFlags::WRITE));
+        assert!(!PropertyFilter::Read.matches(CharPropFlags::WRITE | CharPropFlags::NOTIFY));
+    }
+
+    #[test]
+    fn property_filter_write_matches_either_write_type() {
+        assert!(PropertyFilter::Write.matches(CharPropFlags::WRITE));
+        assert!(PropertyFilter::Write.matches(CharPropFlags::WRITE_WITHOUT_RESPONSE));
+        assert!(!PropertyFilter::Write.matches(CharPropFlags::READ | CharPropFlags::NOTIFY));
+    }
+
+    #[test]
+    fn property_filter_notify_matches_notify_or_indicate() {
+        assert!(PropertyFilter::Notify.matches(CharPropFlags::NOTIFY));
+        assert!(PropertyFilter::Notify.matches(CharPropFlags::INDICATE));
+        assert!(!PropertyFilter::Notify.matches(CharPropFlags::READ | CharPropFlags::WRITE));
+    }
+
+    #[test]
+    fn property_filter_cycles_back_to_all() {
+        let mut filter = PropertyFilter::All;
+        for _ in 0..4 {
+            filter = filter.next();
+        }
+        assert!(filter == PropertyFilter::All);
+    }
+
+    #[test]
+    fn write_type_with_both_defaults_to_with_response() {
+        let characteristic =
+            characteristic(CharPropFlags::WRITE | CharPropFlags::WRITE_WITHOUT_RESPONSE);
+        assert!(characteristic.has_write_choice());
+        assert_eq!(
+            characteristic.write_type(false),
+            Some(WriteType::WithResponse)
+        );
+        assert_eq!(
+            characteristic.write_type(true),
+            Some(WriteType::WithoutResponse)
+        );
+    }
+
+    #[test]
+    fn write_type_is_locked_when_only_one_is_supported() {
+        let with = characteristic(CharPropFlags::WRITE | CharPropFlags::READ);
+        assert!(!with.has_write_choice());
+        assert_eq!(with.write_type(true), Some(WriteType::WithResponse));
+
+        let without = characteristic(CharPropFlags::WRITE_WITHOUT_RESPONSE);
+        assert!(!without.has_write_choice());
+        assert_eq!(without.write_type(false), Some(WriteType::WithoutResponse));
+    }
+
+    #[test]
+    fn write_type_is_none_when_not_writable() {
+        let characteristic = characteristic(CharPropFlags::READ | CharPropFlags::NOTIFY);
+        assert_eq!(characteristic.write_type(false), None);
+        assert_eq!(characteristic.write_type(true), None);
+    }
+
+    #[test]
+    fn subscription_label_marks_notifications() {
+        let notify = characteristic(CharPropFlags::READ | CharPropFlags::NOTIFY);
+        assert_eq!(notify.subscription_label(), Some("Subscribed ●"));
+        // Notify is preferred when both are supported
+        let both = characteristic(CharPropFlags::NOTIFY | CharPropFlags::INDICATE);
+        assert_eq!(both.subscription_label(), Some("Subscribed ●"));
+    }
+
+    #[test]
+    fn subscription_label_marks_indications() {
+        let indicate = characteristic(CharPropFlags::INDICATE);
+        assert_eq!(indicate.subscription_label(), Some("Indicating ●"));
+    }
+
+    #[test]
+    fn subscription_label_is_none_without_notify_or_indicate() {
+        let characteristic = characteristic(CharPropFlags::READ | CharPropFlags::WRITE);
+        assert_eq!(characteristic.subscription_label(), None);
+    }
 }
@@ -1,8 +1,24 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::BuildHasher,
+    time::Duration,
+};
 
+use btleplug::api::CharPropFlags;
 use ratatui::layout::Rect;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use uuid::Uuid;
 
-use crate::{company_codes::COMPANY_CODE, structs::ManufacturerData};
+use crate::{
+    cli::HexFormat,
+    company_codes::COMPANY_CODE,
+    gatt_names::GATT_SERVICES,
+    structs::{DeviceCategory, DeviceInfo, ManufacturerData},
+};
+
+/// The Bluetooth Base UUID, `00000000-0000-1000-8000-00805F9B34FB`, from which 16-bit and
+/// 32-bit UUIDs are derived.
+const BLUETOOTH_BASE_UUID: u128 = 0x00000000_0000_1000_8000_00805F9B34FB;
 
 /// Extracts the manufacturer data from a `HashMap<u16, Vec<u8>>` and returns a tuple with the company name and the manufacturer data as a string.
 /// If the manufacturer data is empty, it returns "n/a" as the company name and the manufacturer data.
@@ -13,12 +29,7 @@ pub fn extract_manufacturer_data(manufacturer_data: &HashMap<u16, Vec<u8>>) -> M
         .iter()
         .map(|(&key, value)| {
             c = Some(key);
-            let hex_string = value
-                .iter()
-                .map(|byte| format!("{:02X}", byte))
-                .collect::<Vec<String>>()
-                .join(" ");
-            hex_string.to_string()
+            bytes_to_hex(value)
         })
         .collect::<Vec<String>>()
         .join(" ");
@@ -35,6 +46,370 @@ pub fn extract_manufacturer_data(manufacturer_data: &HashMap<u16, Vec<u8>>) -> M
     }
 }
 
+/// Formats the bytes as space separated uppercase hex, e.g. `DE AD BE EF`.
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Formats the bytes as lowercase hex without separators, e.g. `deadbeef`, for tools parsing
+/// exports.
+pub fn bytes_to_hex_compact(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Formats characteristic properties as their bitmask, as numbered by the GATT specification,
+/// e.g. `0x1A` for read, write and notify.
+pub fn props_to_hex(properties: CharPropFlags) -> String {
+    format!("0x{:02X}", properties.bits())
+}
+
+/// Formats the bytes as hex in the given format.
+pub fn format_hex(bytes: &[u8], format: HexFormat) -> String {
+    match format {
+        HexFormat::Spaced => bytes_to_hex(bytes),
+        HexFormat::Compact => bytes_to_hex_compact(bytes),
+        HexFormat::Prefixed => format!("0x{}", bytes_to_hex_compact(bytes)),
+    }
+}
+
+/// Parses hex bytes as formatted by `bytes_to_hex`, or as commonly pasted: in any case, with
+/// or without `0x` prefixes, separated by spaces, commas or colons or not at all,
+/// e.g. `DE AD BE EF`, `deadbeef`, `0xDE, 0xAD` or `de:ad`. Each separated token is parsed on
+/// its own, so `0x1,0x2` is two bytes: a token is either a single digit, read as one byte, or
+/// an even number of digits read in pairs.
+pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for token in hex
+        .split(|c: char| c == ',' || c == ':' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+    {
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if let Some(invalid) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid hex character '{}'", invalid));
+        }
+        match digits.len() {
+            0 => return Err(format!("No hex digits in '{}'", token)),
+            1 => bytes.push(u8::from_str_radix(digits, 16).map_err(|e| e.to_string())?),
+            len if !len.is_multiple_of(2) => {
+                return Err(format!(
+                    "Odd number of hex digits in '{}' ({}), expected whole bytes",
+                    token, len
+                ))
+            }
+            // The digits are ASCII, so every pair is on a character boundary
+            len => {
+                for i in (0..len).step_by(2) {
+                    bytes.push(
+                        u8::from_str_radix(&digits[i..i + 2], 16).map_err(|e| e.to_string())?,
+                    );
+                }
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Formats as many of the leading bytes as fit in `max_width` columns with `format`, followed
+/// by an ellipsis and the number of bytes left out, e.g. `DE AD … +2 bytes`. Bytes that fit
+/// entirely are formatted as is.
+pub fn fit_bytes(bytes: &[u8], max_width: usize, format: impl Fn(&[u8]) -> String) -> String {
+    let full = format(bytes);
+    if full.width() <= max_width {
+        return full;
+    }
+    (0..bytes.len())
+        .rev()
+        .map(|shown| {
+            let suffix = format!("… +{} bytes", bytes.len() - shown);
+            match shown {
+                0 => suffix,
+                _ => format!("{} {}", format(&bytes[..shown]), suffix),
+            }
+        })
+        .find(|text| text.width() <= max_width)
+        .unwrap_or_else(|| format!("… +{} bytes", bytes.len()))
+}
+
+/// The RSSI expected at 1 meter when a device does not advertise its TX power.
+pub const DEFAULT_REFERENCE_POWER: i16 = -59;
+
+/// Estimates the distance in meters to a device using the log-distance path-loss model.
+/// `tx_power` is the expected RSSI at 1 meter, falling back to `DEFAULT_REFERENCE_POWER`,
+/// and `n` is the path-loss exponent (2.0 in free space, higher indoors).
+pub fn estimate_distance(rssi: i16, tx_power: Option<i16>, n: f32) -> f32 {
+    let reference = tx_power.unwrap_or(DEFAULT_REFERENCE_POWER) as f32;
+    10f32.powf((reference - rssi as f32) / (10.0 * n))
+}
+
+/// Formats the bytes as ASCII, replacing non-printable bytes with `.`.
+pub fn bytes_to_ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+/// How many bytes `hex_dump` shows on each line.
+const HEX_DUMP_BYTES_PER_LINE: usize = 16;
+
+/// Dumps the bytes as lines of offset, hex and ASCII, e.g. `0000  48 69  |Hi|`.
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(HEX_DUMP_BYTES_PER_LINE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            format!(
+                "{:04X}  {:<width$}  |{}|",
+                i * HEX_DUMP_BYTES_PER_LINE,
+                bytes_to_hex(chunk),
+                bytes_to_ascii(chunk),
+                width = HEX_DUMP_BYTES_PER_LINE * 3 - 1
+            )
+        })
+        .collect()
+}
+
+/// Returns the 16-bit UUID if the UUID is derived from the Bluetooth Base UUID.
+pub fn uuid_to_u16(uuid: &Uuid) -> Option<u16> {
+    let value = uuid.as_u128();
+    let short = (value >> 96) as u32;
+    if value & !(0xFFFF_FFFFu128 << 96) == BLUETOOTH_BASE_UUID && short <= 0xFFFF {
+        Some(short as u16)
+    } else {
+        None
+    }
+}
+
+/// Formats a UUID derived from the Bluetooth Base UUID in its short form, e.g. `0x2A37`, or
+/// `0x0001F00D` for 32-bit ones. Proprietary UUIDs are formatted in full.
+pub fn short_uuid(uuid: &Uuid) -> String {
+    let value = uuid.as_u128();
+    if value & !(0xFFFF_FFFFu128 << 96) != BLUETOOTH_BASE_UUID {
+        return uuid.to_string();
+    }
+    match uuid_to_u16(uuid) {
+        Some(short) => format!("0x{:04X}", short),
+        None => format!("0x{:08X}", value >> 96),
+    }
+}
+
+/// Maps an RSSI to how strong the signal is from 0 to 100, from -100 dBm and weaker up to
+/// -30 dBm and stronger.
+pub fn signal_percent(rssi: f32) -> u16 {
+    ((rssi + 100.0) / 70.0 * 100.0).clamp(0.0, 100.0) as u16
+}
+
+/// Expands a 16-bit or 32-bit UUID against the Bluetooth Base UUID.
+pub fn bluetooth_uuid(short: u32) -> Uuid {
+    Uuid::from_u128(((short as u128) << 96) | BLUETOOTH_BASE_UUID)
+}
+
+/// Parses a UUID typed by the user. Besides the full form, with or without hyphens, 16-bit and
+/// 32-bit UUIDs such as `2a37`, `0x2A37` or `00002a37` are expanded against the Bluetooth Base
+/// UUID.
+pub fn normalize_uuid(input: &str) -> Result<Uuid, String> {
+    let input = input.trim();
+    let digits = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+    let short = match digits.len() {
+        4 | 8 if digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+            u32::from_str_radix(digits, 16).ok()
+        }
+        _ => None,
+    };
+    match short {
+        Some(short) => Ok(bluetooth_uuid(short)),
+        None => Uuid::parse_str(input).map_err(|_| format!("Invalid UUID: {}", input)),
+    }
+}
+
+/// Returns the name of a service assigned by the Bluetooth SIG, or `None` for others.
+pub fn service_name(uuid: &Uuid) -> Option<&'static str> {
+    uuid_to_u16(uuid).and_then(|short| GATT_SERVICES.get(&short).copied())
+}
+
+/// Decodes the service data advertised for a service. Well-known services get a readable
+/// interpretation; anything else falls back to hex with an ASCII preview.
+pub fn decode_service_data(uuid: &Uuid, data: &[u8]) -> String {
+    match (uuid_to_u16(uuid), data) {
+        // Battery Service: the battery level as a percentage
+        (Some(0x180F), [level, ..]) => format!("Battery {}%", level),
+        // Exposure Notification: a 16-byte Rolling Proximity Identifier and 4 bytes of
+        // Associated Encrypted Metadata
+        (Some(0xFD6F), data) if data.len() == 20 => format!(
+            "RPI {} AEM {}",
+            bytes_to_hex(&data[..16]),
+            bytes_to_hex(&data[16..])
+        ),
+        _ => format!("{} |{}|", bytes_to_hex(data), bytes_to_ascii(data)),
+    }
+}
+
+/// The 16-bit UUID of the Device Information Service.
+pub const DEVICE_INFORMATION_UUID: u16 = 0x180A;
+
+/// The string characteristics of the Device Information Service with their labels, in the
+/// order they are shown.
+pub const DEVICE_INFORMATION: [(u16, &str); 6] = [
+    (0x2A29, "Manufacturer"),
+    (0x2A24, "Model Number"),
+    (0x2A25, "Serial Number"),
+    (0x2A27, "Hardware Revision"),
+    (0x2A26, "Firmware Revision"),
+    (0x2A28, "Software Revision"),
+];
+
+/// Returns the label of a string characteristic of the Device Information Service.
+pub fn device_information_label(uuid: &Uuid) -> Option<&'static str> {
+    let short = uuid_to_u16(uuid)?;
+    DEVICE_INFORMATION
+        .iter()
+        .find(|(uuid, _)| *uuid == short)
+        .map(|(_, label)| *label)
+}
+
+/// The 16-bit UUID of the Eddystone service.
+pub const EDDYSTONE_UUID: u16 = 0xFEAA;
+
+/// Decodes an Eddystone-URL frame (frame type `0x10`) from the Eddystone service data into
+/// the full URL, expanding the scheme prefix and the encoded top-level domains.
+/// Returns `None` for other frames or when the URL contains reserved bytes.
+pub fn decode_eddystone_url(data: &[u8]) -> Option<String> {
+    const SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+    const EXPANSIONS: [&str; 14] = [
+        ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu",
+        ".net", ".info", ".biz", ".gov",
+    ];
+
+    let [0x10, _tx_power, scheme, encoded @ ..] = data else {
+        return None;
+    };
+    let mut url = SCHEMES.get(*scheme as usize)?.to_string();
+    for &byte in encoded {
+        match byte {
+            0x00..=0x0D => url.push_str(EXPANSIONS[byte as usize]),
+            0x21..=0x7E => url.push(byte as char),
+            _ => return None,
+        }
+    }
+    Some(url)
+}
+
+/// Guesses what kind of device a device is from its advertised services, its manufacturer
+/// data and, where BlueZ reports it, its Class of Device. btleplug doesn't expose the GAP
+/// Appearance, so that isn't considered.
+pub fn classify_device(device: &DeviceInfo) -> DeviceCategory {
+    let services: Vec<u16> = device
+        .services
+        .iter()
+        .chain(device.service_data.keys())
+        .filter_map(uuid_to_u16)
+        .collect();
+    let has_service = |list: &[u16]| services.iter().any(|short| list.contains(short));
+
+    // Eddystone, or an iBeacon in Apple's manufacturer data
+    if services.contains(&EDDYSTONE_UUID)
+        || device
+            .manufacturer_data
+            .get(&0x004C)
+            .is_some_and(|data| data.starts_with(&[0x02, 0x15]))
+    {
+        return DeviceCategory::Beacon;
+    }
+    // Heart Rate, Running and Cycling Speed and Cadence, Cycling Power, Pulse Oximeter
+    if has_service(&[0x180D, 0x1814, 0x1816, 0x1818, 0x1822]) {
+        return DeviceCategory::Wearable;
+    }
+    // Audio Stream Control, Broadcast Audio Scan, Published Audio Capabilities, Volume Control
+    if has_service(&[0x184E, 0x184F, 0x1850, 0x1844]) {
+        return DeviceCategory::Audio;
+    }
+    // Health Thermometer, Glucose, Blood Pressure, Environmental Sensing, Weight Scale
+    if has_service(&[0x1809, 0x1808, 0x1810, 0x181A, 0x181D]) {
+        return DeviceCategory::Sensor;
+    }
+    // The major device class of a Class of Device
+    match device.properties.class.map(|class| (class >> 8) & 0x1F) {
+        Some(0x02) => return DeviceCategory::Phone,
+        Some(0x04) => return DeviceCategory::Audio,
+        Some(0x07) => return DeviceCategory::Wearable,
+        _ => {}
+    }
+    // Apple, Samsung and Google mostly advertise from phones at this point
+    if [0x004C, 0x0075, 0x00E0]
+        .iter()
+        .any(|company| device.manufacturer_data.contains_key(company))
+    {
+        return DeviceCategory::Phone;
+    }
+    DeviceCategory::Unknown
+}
+
+/// Redacts an address for sharing: the last three octets of a MAC address, or the last group
+/// of a UUID, are replaced with `XX`, e.g. `AA:BB:CC:XX:XX:XX#1F2E3D`. The appended hash of the
+/// full address is keyed with the session's random `key`, so devices can still be told apart
+/// within a capture, but the redacted part can't be recovered by hashing every candidate.
+pub fn anonymize_address(address: &str, key: &RandomState) -> String {
+    let hash = key.hash_one(address.to_ascii_uppercase());
+    let octets: Vec<&str> = address.split(':').collect();
+    let redacted = if octets.len() == 6 {
+        format!("{}:XX:XX:XX", octets[..3].join(":"))
+    } else if let Ok(uuid) = Uuid::parse_str(address) {
+        let uuid = uuid.hyphenated().to_string();
+        format!("{}-XX", &uuid[..23])
+    } else {
+        "XX".to_owned()
+    };
+    format!("{}#{:06X}", redacted, hash & 0xFF_FFFF)
+}
+
+/// Makes an advertised name safe to render in a fixed-width column: control characters are
+/// replaced with `�` and the name is truncated with `…` to fit `max_width` terminal columns,
+/// accounting for wide characters such as emoji.
+pub fn sanitize_name(name: &str, max_width: usize) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() { '\u{FFFD}' } else { c })
+        .collect();
+    if name.width() <= max_width {
+        return name;
+    }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in name.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width + 1 > max_width {
+            break;
+        }
+        width += char_width;
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Formats a duration as `HH:MM:SS`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
 /// Returns a `Rect` with the provided percentage of the parent `Rect` and centered.
 pub fn centered_rect(percent_x: u16, percent_y: u16, size: Rect) -> Rect {
     let popup_size = Rect {
@@ -48,3 +423,333 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, size: Rect) -> Rect {
         ..popup_size
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymize_address_redacts_the_last_octets_of_a_mac_address() {
+        let key = RandomState::new();
+        let anonymized = anonymize_address("AA:BB:CC:DD:EE:FF", &key);
+        let (redacted, hash) = anonymized.split_once('#').unwrap();
+        assert_eq!(redacted, "AA:BB:CC:XX:XX:XX");
+        assert_eq!(hash.len(), 6);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn anonymize_address_redacts_the_last_group_of_a_uuid() {
+        let key = RandomState::new();
+        let anonymized = anonymize_address("4B0D8E5C-1A2B-4C3D-8E9F-0123456789AB", &key);
+        assert!(anonymized.starts_with("4b0d8e5c-1a2b-4c3d-8e9f-XX#"));
+        assert!(!anonymized.contains("0123456789"));
+    }
+
+    #[test]
+    fn anonymize_address_redacts_other_identifiers_entirely() {
+        let key = RandomState::new();
+        assert!(anonymize_address("hci0/dev_42", &key).starts_with("XX#"));
+    }
+
+    #[test]
+    fn anonymize_address_is_stable_within_a_session() {
+        let key = RandomState::new();
+        assert_eq!(
+            anonymize_address("AA:BB:CC:DD:EE:FF", &key),
+            anonymize_address("AA:BB:CC:DD:EE:FF", &key)
+        );
+        assert_ne!(
+            anonymize_address("AA:BB:CC:DD:EE:FF", &key),
+            anonymize_address("AA:BB:CC:DD:EE:FE", &key)
+        );
+    }
+
+    #[test]
+    fn format_hex_writes_each_format() {
+        let bytes = [0xDE, 0xAD, 0xBE, 0xEF];
+        assert_eq!(format_hex(&bytes, HexFormat::Spaced), "DE AD BE EF");
+        assert_eq!(format_hex(&bytes, HexFormat::Compact), "deadbeef");
+        assert_eq!(format_hex(&bytes, HexFormat::Prefixed), "0xdeadbeef");
+    }
+
+    #[test]
+    fn every_hex_format_parses_back() {
+        let bytes = vec![0x00, 0x0A, 0xFF];
+        for format in [HexFormat::Spaced, HexFormat::Compact, HexFormat::Prefixed] {
+            assert_eq!(hex_to_bytes(&format_hex(&bytes, format)), Ok(bytes.clone()));
+        }
+    }
+
+    #[test]
+    fn hex_round_trips_through_bytes_to_hex() {
+        for bytes in [
+            vec![],
+            vec![0x00],
+            vec![0xDE, 0xAD, 0xBE, 0xEF],
+            (0..=255).collect(),
+        ] {
+            assert_eq!(hex_to_bytes(&bytes_to_hex(&bytes)), Ok(bytes));
+        }
+    }
+
+    #[test]
+    fn hex_to_bytes_accepts_unseparated_digits() {
+        assert_eq!(hex_to_bytes("deadBEEF"), Ok(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+    }
+
+    #[test]
+    fn hex_to_bytes_parses_empty_input_as_no_bytes() {
+        assert_eq!(hex_to_bytes(""), Ok(vec![]));
+        assert_eq!(hex_to_bytes("   "), Ok(vec![]));
+    }
+
+    #[test]
+    fn hex_to_bytes_rejects_odd_digit_counts() {
+        assert!(hex_to_bytes("ABC").is_err());
+        assert!(hex_to_bytes("DE ADB").is_err());
+    }
+
+    #[test]
+    fn hex_to_bytes_accepts_prefixes_and_separators() {
+        assert_eq!(hex_to_bytes("0x00,0xFF"), Ok(vec![0x00, 0xFF]));
+        assert_eq!(hex_to_bytes("0x00, 0xFF"), Ok(vec![0x00, 0xFF]));
+        assert_eq!(hex_to_bytes("00:ff"), Ok(vec![0x00, 0xFF]));
+        assert_eq!(hex_to_bytes("00 FF"), Ok(vec![0x00, 0xFF]));
+        assert_eq!(hex_to_bytes(" 0XaB "), Ok(vec![0xAB]));
+    }
+
+    #[test]
+    fn hex_to_bytes_parses_each_separated_token_on_its_own() {
+        assert_eq!(hex_to_bytes("0x1,0x2"), Ok(vec![0x01, 0x02]));
+        assert_eq!(hex_to_bytes("A B C D"), Ok(vec![0x0A, 0x0B, 0x0C, 0x0D]));
+        assert_eq!(hex_to_bytes("1 0203"), Ok(vec![0x01, 0x02, 0x03]));
+    }
+
+    #[test]
+    fn hex_to_bytes_rejects_a_prefix_without_digits() {
+        assert!(hex_to_bytes("0x").is_err());
+        assert!(hex_to_bytes("01 0x").is_err());
+    }
+
+    #[test]
+    fn hex_to_bytes_rejects_invalid_characters() {
+        assert_eq!(
+            hex_to_bytes("DE AZ"),
+            Err("Invalid hex character 'Z'".to_owned())
+        );
+        assert!(hex_to_bytes("DE-AD").is_err());
+        assert_eq!(
+            hex_to_bytes("0G"),
+            Err("Invalid hex character 'G'".to_owned())
+        );
+    }
+
+    #[test]
+    fn estimate_distance_at_known_points() {
+        assert!((estimate_distance(-59, Some(-59), 2.0) - 1.0).abs() < 1e-4);
+        assert!((estimate_distance(-79, Some(-59), 2.0) - 10.0).abs() < 1e-3);
+        assert!((estimate_distance(-99, Some(-59), 4.0) - 10.0).abs() < 1e-3);
+        assert!((estimate_distance(-49, Some(-59), 2.0) - 0.316).abs() < 1e-3);
+    }
+
+    #[test]
+    fn estimate_distance_defaults_the_reference_power() {
+        assert_eq!(
+            estimate_distance(-69, None, 2.0),
+            estimate_distance(-69, Some(DEFAULT_REFERENCE_POWER), 2.0)
+        );
+    }
+
+    #[test]
+    fn decode_service_data_decodes_the_battery_level() {
+        assert_eq!(
+            decode_service_data(&bluetooth_uuid(0x180F), &[0x55]),
+            "Battery 85%"
+        );
+    }
+
+    #[test]
+    fn decode_service_data_decodes_exposure_notifications() {
+        let data: Vec<u8> = (0..20).collect();
+        assert_eq!(
+            decode_service_data(&bluetooth_uuid(0xFD6F), &data),
+            format!(
+                "RPI {} AEM {}",
+                bytes_to_hex(&data[..16]),
+                bytes_to_hex(&data[16..])
+            )
+        );
+    }
+
+    #[test]
+    fn decode_service_data_falls_back_to_hex() {
+        assert_eq!(
+            decode_service_data(&bluetooth_uuid(0x1234), b"Hi\x01"),
+            "48 69 01 |Hi.|"
+        );
+        // Too short for an Exposure Notification payload
+        assert_eq!(
+            decode_service_data(&bluetooth_uuid(0xFD6F), &[0xAB]),
+            "AB |.|"
+        );
+        assert_eq!(decode_service_data(&bluetooth_uuid(0x180F), &[]), " ||");
+    }
+
+    #[test]
+    fn decode_eddystone_url_expands_the_scheme_and_domain() {
+        let frame = [&[0x10, 0xEB, 0x03][..], b"example", &[0x07]].concat();
+        assert_eq!(
+            decode_eddystone_url(&frame).as_deref(),
+            Some("https://example.com")
+        );
+        let frame = [&[0x10, 0xEB, 0x00][..], b"goo", &[0x00], b"maps"].concat();
+        assert_eq!(
+            decode_eddystone_url(&frame).as_deref(),
+            Some("http://www.goo.com/maps")
+        );
+    }
+
+    #[test]
+    fn decode_eddystone_url_rejects_other_frames() {
+        // An Eddystone-UID frame
+        assert_eq!(decode_eddystone_url(&[0x00, 0xEB, 0x01, 0x02]), None);
+        // An unknown scheme
+        assert_eq!(decode_eddystone_url(&[0x10, 0xEB, 0x04, b'a']), None);
+        // A reserved byte
+        assert_eq!(decode_eddystone_url(&[0x10, 0xEB, 0x03, b'a', 0x0E]), None);
+        assert_eq!(decode_eddystone_url(&[0x10, 0xEB]), None);
+    }
+
+    #[test]
+    fn sanitize_name_replaces_control_characters() {
+        assert_eq!(
+            sanitize_name("Bad\0\nName\x1b", 30),
+            "Bad\u{FFFD}\u{FFFD}Name\u{FFFD}"
+        );
+    }
+
+    #[test]
+    fn sanitize_name_keeps_names_that_fit() {
+        assert_eq!(sanitize_name("温度センサー", 12), "温度センサー");
+        assert_eq!(sanitize_name("Polar H10", 30), "Polar H10");
+    }
+
+    #[test]
+    fn sanitize_name_truncates_long_names() {
+        let name = "A".repeat(40);
+        let sanitized = sanitize_name(&name, 30);
+        assert_eq!(sanitized, format!("{}…", "A".repeat(29)));
+        assert_eq!(sanitized.width(), 30);
+    }
+
+    #[test]
+    fn sanitize_name_truncates_cjk_names_by_display_width() {
+        let sanitized = sanitize_name("温度センサー", 7);
+        assert_eq!(sanitized, "温度セ…");
+        assert_eq!(sanitized.width(), 7);
+        // An odd width leaves a column free rather than splitting a wide character
+        let sanitized = sanitize_name("温度センサー", 8);
+        assert_eq!(sanitized, "温度セ…");
+        assert!(sanitized.width() <= 8);
+    }
+
+    #[test]
+    fn sanitize_name_truncates_emoji_names_by_display_width() {
+        let sanitized = sanitize_name("🔥🔥🔥🔥 Beacon", 5);
+        assert_eq!(sanitized, "🔥🔥…");
+        assert_eq!(sanitized.width(), 5);
+    }
+
+    fn classified(properties: btleplug::api::PeripheralProperties) -> &'static str {
+        classify_device(&DeviceInfo::new_synthetic(properties)).label()
+    }
+
+    #[test]
+    fn classify_device_by_service() {
+        let services = |uuids: &[u16]| btleplug::api::PeripheralProperties {
+            services: uuids
+                .iter()
+                .map(|&uuid| bluetooth_uuid(uuid as u32))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(classified(services(&[0x180D])), "Wearable");
+        assert_eq!(classified(services(&[0x180F, 0x1809])), "Sensor");
+        assert_eq!(classified(services(&[0x1844])), "Audio");
+        assert_eq!(classified(services(&[0xFEAA])), "Beacon");
+    }
+
+    #[test]
+    fn classify_device_by_manufacturer_data() {
+        let manufacturer = |company: u16, data: &[u8]| btleplug::api::PeripheralProperties {
+            manufacturer_data: HashMap::from([(company, data.to_vec())]),
+            ..Default::default()
+        };
+        assert_eq!(
+            classified(manufacturer(0x004C, &[0x02, 0x15, 0x00])),
+            "Beacon"
+        );
+        assert_eq!(classified(manufacturer(0x004C, &[0x10, 0x05])), "Phone");
+    }
+
+    #[test]
+    fn classify_device_by_class_of_device() {
+        let properties = btleplug::api::PeripheralProperties {
+            class: Some(0x5A020C),
+            ..Default::default()
+        };
+        assert_eq!(classified(properties), "Phone");
+    }
+
+    #[test]
+    fn classify_device_leaves_unknown_devices_unknown() {
+        assert_eq!(classified(Default::default()), "?");
+    }
+
+    #[test]
+    fn short_uuid_shortens_uuids_derived_from_the_base_uuid() {
+        assert_eq!(short_uuid(&bluetooth_uuid(0x2A37)), "0x2A37");
+        assert_eq!(short_uuid(&bluetooth_uuid(0x0001F00D)), "0x0001F00D");
+    }
+
+    #[test]
+    fn short_uuid_keeps_proprietary_uuids_in_full() {
+        let uuid = Uuid::parse_str("6e400001-b5a3-f393-e0a9-e50e24dcca9e").unwrap();
+        assert_eq!(short_uuid(&uuid), "6e400001-b5a3-f393-e0a9-e50e24dcca9e");
+    }
+
+    #[test]
+    fn normalize_uuid_expands_short_forms() {
+        let heart_rate_measurement = bluetooth_uuid(0x2A37);
+        for input in ["2a37", "0x2A37", "0X2a37", "00002a37", " 2A37 "] {
+            assert_eq!(
+                normalize_uuid(input),
+                Ok(heart_rate_measurement),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(normalize_uuid("0001F00D"), Ok(bluetooth_uuid(0x0001F00D)));
+    }
+
+    #[test]
+    fn normalize_uuid_accepts_full_forms() {
+        let heart_rate = bluetooth_uuid(0x180D);
+        assert_eq!(
+            normalize_uuid("0000180d-0000-1000-8000-00805f9b34fb"),
+            Ok(heart_rate)
+        );
+        assert_eq!(
+            normalize_uuid("0000180D00001000800000805F9B34FB"),
+            Ok(heart_rate)
+        );
+    }
+
+    #[test]
+    fn normalize_uuid_rejects_invalid_input() {
+        for input in ["", "2a3", "12345", "0xZZZZ", "not-a-uuid"] {
+            assert!(normalize_uuid(input).is_err(), "{}", input);
+        }
+    }
+}
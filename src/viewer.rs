@@ -1,23 +1,42 @@
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::execute;
+use crossterm::style::Print;
 use ratatui::backend::Backend;
-use ratatui::layout::Alignment;
-use ratatui::text::Span;
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, TableState};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     Terminal,
 };
 use std::error::Error;
 use std::sync::atomic::Ordering;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::app::{App, DeviceData};
-use crate::structs::DeviceInfo;
+use crate::app::{App, Focus, InputMode, InputTarget};
+use crate::keymap::Action;
+use crate::structs::{DeviceFilter, DeviceInfo};
 use crate::utils::centered_rect;
+use crate::widgets::connect_gauge::connect_gauge;
+use crate::widgets::connections_panel::connections_panel;
 use crate::widgets::detail_table::detail_table;
 use crate::widgets::device_table::device_table;
 use crate::widgets::info_table::info_table;
 use crate::widgets::inspect_overlay::inspect_overlay;
+use crate::widgets::level_gauge::level_gauge;
+use crate::widgets::locate_gauge::locate_gauge;
+use crate::widgets::message_log::message_log;
+use crate::widgets::nus_terminal::nus_terminal;
+use crate::widgets::payload_overlay::payload_overlay;
+use crate::widgets::raw_properties_overlay::raw_properties_overlay;
+use crate::widgets::scrollbar::render_scrollbar;
+use crate::widgets::service_picker::service_picker;
+use crate::widgets::value_editor::value_editor;
+
+/// The most updates handled between two looks at the input, so a flood of advertisements
+/// doesn't hold up key presses and redraws. The rest wait for the next tick.
+const MAX_UPDATES_PER_TICK: usize = 64;
 
 /// Displays the detected Bluetooth devices in a table and handles the user input.
 /// The user can navigate the table, pause the scanning, and quit the application.
@@ -27,154 +46,735 @@ pub async fn viewer<B: Backend>(
     app: &mut App,
 ) -> Result<(), Box<dyn Error>> {
     app.table_state.select(Some(0));
+    // Set while updates are left over from the last tick, so the next tick handles them
+    // without waiting for input
+    let mut backlog = false;
 
     loop {
-        // Draw UI
-        terminal.draw(|f| {
-            app.frame_count = f.count();
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .margin(1)
-                .constraints(
-                    [
-                        Constraint::Percentage(70),
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(10),
-                    ]
-                    .as_ref(),
-                )
-                .split(f.size());
-
-            let device_binding = &DeviceInfo::default();
-            let selected_device = app
-                .devices
-                .get(app.table_state.selected().unwrap_or(0))
-                .unwrap_or(device_binding);
-
-            // Draw the device table
-            let device_table = device_table(app.table_state.selected(), &app.devices);
-            f.render_stateful_widget(device_table, chunks[0], &mut app.table_state);
-
-            // Draw the detail table
-            let detail_table = detail_table(selected_device);
-            f.render_widget(detail_table, chunks[1]);
-
-            // Draw the info table
-            app.frame_count += 1;
-            let info_table: ratatui::widgets::Table<'_> = info_table(
-                app.pause_status.load(Ordering::SeqCst),
-                &app.is_loading,
-                &app.frame_count,
-            );
-            f.render_widget(info_table, chunks[2]);
-
-            // Draw the inspect overlay
-            if app.inspect_view {
-                let area = centered_rect(60, 60, f.size());
-                let inspect_overlay = inspect_overlay(
-                    &app.selected_characteristics,
-                    app.inspect_overlay_scroll,
-                    area.height,
+        app.publish_status();
+        app.auto_read_tick();
+        app.auto_export_tick();
+
+        // Draw UI, but only when something changed so an idle UI doesn't burn CPU
+        if app.needs_redraw() {
+            app.dirty = false;
+            app.last_draw = Instant::now();
+            // Advance the spinner on its own tick, independently of the terminal's frame count
+            app.tick_spinner();
+            terminal.draw(|f| {
+                // The dense layout gives the room of the details and the message log to the
+                // device table, keeping the info bar
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(1)
+                    .constraints(if app.dense_layout {
+                        [
+                            Constraint::Percentage(90),
+                            Constraint::Length(0),
+                            Constraint::Percentage(10),
+                        ]
+                    } else {
+                        [
+                            Constraint::Percentage(70),
+                            Constraint::Percentage(20),
+                            Constraint::Percentage(10),
+                        ]
+                    })
+                    .split(f.size());
+
+                let visible_devices: Vec<&DeviceInfo> = if app.display_frozen {
+                    app.frozen_devices.iter().collect()
+                } else {
+                    app.visible_indices()
+                        .into_iter()
+                        .map(|i| &app.devices[i])
+                        .collect()
+                };
+                let selected_device = visible_devices
+                    .get(app.table_state.selected().unwrap_or(0))
+                    .copied();
+
+                // Draw the device table
+                let device_table = device_table(
+                    app.table_state.selected(),
+                    &visible_devices,
+                    &app.favorites,
+                    &app.selected_ids,
+                    &app.columns,
+                    |device| app.estimate_distance(device),
+                    |device, column| !app.display_frozen && app.recently_changed(device, column),
+                    app.sort_key,
+                    app.sort_ascending,
+                    app.display_frozen,
+                    app.anonymize_display.then_some(&app.anonymize_key),
+                    app.focus == Focus::Devices,
+                    app.flashing(),
                 );
-                f.render_widget(Clear, area);
-                f.render_widget(inspect_overlay, area);
-            }
+                f.render_stateful_widget(device_table, chunks[0], &mut app.table_state);
+
+                // Prompt to start scanning while paused before anything was found
+                if app.devices.is_empty()
+                    && !app.is_loading
+                    && app.pause_status.load(Ordering::SeqCst)
+                {
+                    let area = Rect {
+                        x: chunks[0].x,
+                        y: chunks[0].y + chunks[0].height / 2,
+                        width: chunks[0].width,
+                        height: 1,
+                    };
+                    let prompt = Paragraph::new("Scanning is paused. Press s to start scanning.")
+                        .alignment(Alignment::Center)
+                        .style(Style::default().fg(Color::Yellow));
+                    f.render_widget(prompt, area);
+                }
+
+                let detail_chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+                    .split(chunks[1]);
+
+                // Draw the detail table, in an overlay on demand with the dense layout
+                let detail_area = if app.dense_layout {
+                    app.details_view.then(|| centered_rect(80, 50, f.size()))
+                } else {
+                    Some(detail_chunks[0])
+                };
+                let mut detail_scroll = app.detail_scroll;
+                let detail_table = detail_table(
+                    selected_device,
+                    selected_device.and_then(|device| app.connections.get(&device.id)),
+                    selected_device.and_then(|device| app.notes.get(&device.id)),
+                    selected_device.and_then(|device| app.estimate_distance(device)),
+                    selected_device.and_then(|device| app.advertising_interval(device)),
+                    selected_device.and_then(|device| app.gatt_tx_power(device)),
+                    app.manufacturer_ascii,
+                    &mut detail_scroll,
+                    app.focus == Focus::Details || app.details_view,
+                    detail_area.map_or(0, |area| area.width),
+                );
+                app.detail_scroll = detail_scroll;
+                if let Some(detail_area) = detail_area {
+                    f.render_widget(Clear, detail_area);
+                    // Show the battery level as a gauge under the details when it is known
+                    match selected_device.and_then(|device| app.battery_level(device)) {
+                        Some(level) => {
+                            let gauge_chunks = Layout::default()
+                                .direction(Direction::Vertical)
+                                .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+                                .split(detail_area);
+                            f.render_widget(detail_table, gauge_chunks[0]);
+                            f.render_widget(level_gauge("Battery Level", level), gauge_chunks[1]);
+                        }
+                        None => f.render_widget(detail_table, detail_area),
+                    }
+                }
+
+                // Draw the message log, hidden with the dense layout
+                if !app.dense_layout {
+                    let logs = app.visible_logs();
+                    let message_log = message_log(
+                        &logs,
+                        &app.log_filter,
+                        detail_chunks[1].height,
+                        app.log_scroll,
+                        app.focus == Focus::Log,
+                    );
+                    f.render_widget(message_log, detail_chunks[1]);
+                    let visible_logs = detail_chunks[1].height.saturating_sub(2) as usize;
+                    render_scrollbar(
+                        f,
+                        detail_chunks[1],
+                        logs.len()
+                            .saturating_sub(app.log_scroll)
+                            .saturating_sub(visible_logs),
+                        logs.len(),
+                        visible_logs,
+                    );
+                }
+
+                // Draw the info table, or the prompt while typing into it
+                if app.input_mode == InputMode::Editing
+                    && !matches!(
+                        app.input_target,
+                        InputTarget::NusTerminal | InputTarget::Write | InputTarget::WriteVerify
+                    )
+                {
+                    let label = match app.input_target {
+                        InputTarget::Note => "Note: ",
+                        InputTarget::ConnectAddress => "Connect to: ",
+                        InputTarget::ScanService => "Service UUID: ",
+                        InputTarget::NameFilter => "Name (regex): ",
+                        InputTarget::LogFilter => "Log filter: ",
+                        _ => ":",
+                    };
+                    let mut prompt = vec![Span::styled(
+                        format!("{}{}█", label, app.input_buffer),
+                        Style::default().fg(Color::Yellow),
+                    )];
+                    if let Some(error) = &app.name_filter_error {
+                        prompt.push(Span::styled(
+                            format!("  {}", error),
+                            Style::default().fg(Color::Red),
+                        ));
+                    }
+                    let prompt = Paragraph::new(Line::from(prompt));
+                    f.render_widget(prompt, chunks[2]);
+                } else {
+                    let info_table: ratatui::widgets::Table<'_> = info_table(
+                        app.pause_status.load(Ordering::SeqCst),
+                        &app.is_loading,
+                        &app.frame_count,
+                        app.dropped_events.load(Ordering::SeqCst),
+                        app.evicted_devices,
+                        app.duplicate_discoveries,
+                        app.recorder.is_some(),
+                        &app.active_filters(),
+                        &app.scan_service_labels(),
+                    );
+                    f.render_widget(info_table, chunks[2]);
+                }
+
+                // Draw the connection timeout progress
+                if let (true, Some(connect_started)) = (app.is_loading, app.connect_started) {
+                    let size = f.size();
+                    let area = Rect {
+                        x: size.width / 4,
+                        y: size.height.saturating_sub(3) / 2,
+                        width: size.width / 2,
+                        height: 3.min(size.height),
+                    };
+                    f.render_widget(Clear, area);
+                    f.render_widget(
+                        connect_gauge(connect_started.elapsed(), app.connect_timeout),
+                        area,
+                    );
+                }
+
+                // Draw the inspect overlay
+                if app.inspect_view {
+                    let area = centered_rect(60, 60, f.size());
+                    let subscriptions = app
+                        .inspected_device
+                        .as_ref()
+                        .and_then(|device| app.connections.get(&device.id))
+                        .map(|connection| &connection.subscriptions);
+                    let mut scroll = app.inspect_overlay_scroll;
+                    let (inspect_overlay, total_rows) = inspect_overlay(
+                        &app.visible_characteristics(),
+                        app.property_filter,
+                        subscriptions,
+                        &app.char_values,
+                        &app.char_values_at,
+                        &app.write_without_response,
+                        app.characteristic_selected,
+                        &mut scroll,
+                        area.height,
+                        app.short_uuids,
+                        app.props_hex,
+                        app.auto_read,
+                    );
+                    app.inspect_overlay_scroll = scroll;
+                    f.render_widget(Clear, area);
+                    f.render_widget(inspect_overlay, area);
+                    render_scrollbar(
+                        f,
+                        area,
+                        app.inspect_overlay_scroll,
+                        total_rows,
+                        area.height.saturating_sub(3) as usize,
+                    );
+                }
+
+                // Draw the Nordic UART terminal
+                if app.nus_view {
+                    let area = centered_rect(70, 70, f.size());
+                    let nus_terminal = nus_terminal(
+                        &app.nus_lines,
+                        &app.input_buffer,
+                        app.nus_tx_characteristic()
+                            .and_then(|characteristic| characteristic.subscription_label()),
+                        area.height,
+                    );
+                    f.render_widget(Clear, area);
+                    f.render_widget(nus_terminal, area);
+                }
+
+                // Draw the connections panel
+                if app.connections_view {
+                    let area = centered_rect(70, 40, f.size());
+                    let connections_panel = connections_panel(
+                        &app.connection_list(),
+                        app.connections_selected,
+                        app.anonymize_display.then_some(&app.anonymize_key),
+                    );
+                    f.render_widget(Clear, area);
+                    f.render_widget(connections_panel, area);
+                }
+
+                // Draw the locate gauge
+                if let Some(id) = &app.locate_id {
+                    let area = centered_rect(70, 30, f.size());
+                    let name = app
+                        .devices
+                        .iter()
+                        .find(|device| device.id == *id)
+                        .map_or("device", |device| device.name.as_str());
+                    let locate_gauge =
+                        locate_gauge(name, app.locate_rssi(), app.locate_trend, app.locate_beep);
+                    f.render_widget(Clear, area);
+                    f.render_widget(locate_gauge, area);
+                }
 
-            // Draw the error overlay
-            if app.error_view {
-                let error_message_clone = app.error_message.clone();
-                let area = centered_rect(60, 10, f.size());
-                let error_block = Paragraph::new(Span::from(error_message_clone))
+                // Draw the service picker
+                if app.service_picker_view {
+                    let area = centered_rect(70, 60, f.size());
+                    let service_picker =
+                        service_picker(&app.service_picker_services, &app.service_picker_chosen);
+                    let mut state =
+                        TableState::default().with_selected(Some(app.service_picker_selected));
+                    f.render_widget(Clear, area);
+                    f.render_stateful_widget(service_picker, area, &mut state);
+                }
+
+                // Draw the raw properties overlay
+                if let (true, Some(selected_device)) = (app.raw_properties_view, selected_device) {
+                    let area = centered_rect(60, 60, f.size());
+                    let raw_properties_overlay = raw_properties_overlay(
+                        &selected_device.properties,
+                        app.anonymize_display.then_some(&app.anonymize_key),
+                        app.raw_properties_scroll,
+                        area.height,
+                    );
+                    f.render_widget(Clear, area);
+                    f.render_widget(raw_properties_overlay, area);
+                }
+
+                // Draw the manufacturer data overlay
+                if let (true, Some(selected_device)) = (app.payload_view, selected_device) {
+                    let area = centered_rect(60, 60, f.size());
+                    let payload_overlay =
+                        payload_overlay(&selected_device.manufacturer_data, app.payload_scroll);
+                    f.render_widget(Clear, area);
+                    f.render_widget(payload_overlay, area);
+                }
+
+                // Draw the editor of the value written to a characteristic
+                if app.input_mode == InputMode::Editing
+                    && matches!(
+                        app.input_target,
+                        InputTarget::Write | InputTarget::WriteVerify
+                    )
+                {
+                    let area = centered_rect(60, 50, f.size());
+                    f.render_widget(Clear, area);
+                    f.render_widget(
+                        value_editor(
+                            &app.input_buffer,
+                            app.input_target == InputTarget::WriteVerify,
+                            area,
+                        ),
+                        area,
+                    );
+                }
+
+                // Draw the error overlay
+                if app.error_view {
+                    let error_message_clone = app.error_message.clone();
+                    let area = centered_rect(60, 10, f.size());
+                    let error_block = Paragraph::new(Span::from(error_message_clone))
+                        .alignment(Alignment::Center)
+                        .block(Block::default().borders(Borders::ALL).title("Notification"));
+                    f.render_widget(Clear, area);
+                    f.render_widget(error_block, area);
+                }
+
+                // Draw the quit confirmation
+                if app.quit_confirm_view {
+                    let area = centered_rect(60, 10, f.size());
+                    let confirm_block = Paragraph::new(Span::from(format!(
+                        "Quit while {} connected? Connected devices are disconnected first. y/n",
+                        match app.connections.len() {
+                            1 => "a device is".to_owned(),
+                            count => format!("{} devices are", count),
+                        }
+                    )))
                     .alignment(Alignment::Center)
-                    .block(Block::default().borders(Borders::ALL).title("Notification"));
-                f.render_widget(Clear, area);
-                f.render_widget(error_block, area);
-            }
-        })?;
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Quit")
+                            .border_style(Style::default().fg(Color::Yellow)),
+                    );
+                    f.render_widget(Clear, area);
+                    f.render_widget(confirm_block, area);
+                }
+            })?;
+        }
+
+        // Ring the bell for a newly found match, or as the located device gets closer
+        if app.locate_tick() || app.bell_pending {
+            app.bell_pending = false;
+            execute!(std::io::stdout(), Print('\x07'))?;
+        }
 
         // Event handling
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => {
-                        break;
+        let poll_interval = if backlog {
+            Duration::ZERO
+        } else {
+            app.poll_interval()
+        };
+        if event::poll(poll_interval)? {
+            let event = event::read()?;
+            // Key presses and resizes all change what is shown
+            app.dirty = true;
+            if let Event::Paste(text) = &event {
+                // Paste into the input as if typed, without the line breaks ending the input
+                if app.input_mode == InputMode::Editing {
+                    app.last_activity = Instant::now();
+                    app.input_buffer
+                        .extend(text.chars().filter(|c| !c.is_control()));
+                    app.on_input_changed();
+                }
+            } else if let Event::Key(
+                key @ KeyEvent {
+                    // Only presses act, so keys don't act twice where releases are reported too
+                    kind: KeyEventKind::Press,
+                    ..
+                },
+            ) = event
+            {
+                app.last_activity = Instant::now();
+                if app.input_mode == InputMode::Editing {
+                    match key.code {
+                        KeyCode::Enter if app.error_view => {
+                            app.error_view = false;
+                        }
+                        KeyCode::Enter => match app.input_target {
+                            InputTarget::NusTerminal => app.send_nus_line(),
+                            InputTarget::GoTo => app.stop_editing(),
+                            InputTarget::Note => app.save_note(),
+                            InputTarget::ConnectAddress => app.connect_by_address().await,
+                            InputTarget::Write | InputTarget::WriteVerify => {
+                                app.write_selected_characteristic()
+                            }
+                            InputTarget::ScanService => app.add_picked_service(),
+                            InputTarget::NameFilter => app.save_name_filter(),
+                            InputTarget::LogFilter => app.stop_editing(),
+                        },
+                        KeyCode::Esc => match app.input_target {
+                            InputTarget::NusTerminal => app.close_nus_terminal(),
+                            InputTarget::NameFilter => app.cancel_name_filter(),
+                            InputTarget::LogFilter => app.clear_log_filter(),
+                            InputTarget::GoTo
+                            | InputTarget::Note
+                            | InputTarget::ConnectAddress
+                            | InputTarget::Write
+                            | InputTarget::WriteVerify
+                            | InputTarget::ScanService => app.stop_editing(),
+                        },
+                        KeyCode::Backspace => {
+                            app.input_buffer.pop();
+                            app.on_input_changed();
+                        }
+                        KeyCode::Char(c) => {
+                            app.input_buffer.push(c);
+                            app.on_input_changed();
+                        }
+                        _ => {}
                     }
-                    KeyCode::Char('s') => {
-                        let current_state = app.pause_status.load(Ordering::SeqCst);
-                        app.pause_status.store(!current_state, Ordering::SeqCst);
+                } else if app.locate_id.is_some() {
+                    match key.code {
+                        KeyCode::Char('b') => app.locate_beep = !app.locate_beep,
+                        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('g') => app.toggle_locate(),
+                        _ => {}
                     }
-                    KeyCode::Char('e') => {
-                        app.error_message = match app.get_devices_csv() {
-                            Ok(success_message) => success_message,
-                            Err(e) => e.to_string(),
-                        };
-                        app.error_view = true;
+                } else if app.service_picker_view {
+                    match key.code {
+                        KeyCode::Down | KeyCode::Char('j') => app.move_service_picker_selection(1),
+                        KeyCode::Up | KeyCode::Char('k') => app.move_service_picker_selection(-1),
+                        KeyCode::Char(' ') => app.toggle_picked_service(),
+                        KeyCode::Char('a') => app.start_editing(InputTarget::ScanService),
+                        KeyCode::Enter => app.apply_service_picker().await,
+                        KeyCode::Esc => app.service_picker_view = false,
+                        _ => {}
                     }
-                    KeyCode::Enter => {
-                        if app.error_view {
-                            app.error_view = false;
-                        } else if app.inspect_view {
-                            app.inspect_view = false;
-                        } else {
-                            app.is_loading = true;
-                            app.connect().await;
+                } else if key.code == KeyCode::Esc && app.connect_started.is_some() {
+                    app.cancel_connect();
+                } else if app.quit_confirm_view {
+                    match key.code {
+                        KeyCode::Char('y') => {
+                            app.shutdown().await;
+                            break;
                         }
+                        _ => app.quit_confirm_view = false,
                     }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if app.inspect_view {
-                            app.inspect_overlay_scroll += 1;
-                        } else if !app.devices.is_empty() {
-                            let next = match app.table_state.selected() {
-                                Some(selected) => {
-                                    if selected >= app.devices.len() - 1 {
-                                        0
-                                    } else {
-                                        selected + 1
-                                    }
-                                }
-                                None => 0,
+                } else {
+                    match app
+                        .keymap
+                        .action(key.code, app.inspect_view, app.connections_view)
+                    {
+                        // Shows the quit confirmation instead when devices are connected
+                        Some(Action::Quit) if app.request_quit() => {
+                            app.shutdown().await;
+                            break;
+                        }
+                        Some(Action::Pause) => {
+                            app.toggle_pause();
+                        }
+                        // The panels after the device table are hidden with the dense layout
+                        Some(Action::Focus) if !app.dense_layout => {
+                            app.focus = app.focus.next();
+                        }
+                        Some(Action::FilterManufacturerData) => {
+                            app.toggle_device_filter(DeviceFilter::ManufacturerData);
+                        }
+                        Some(Action::FilterServices) => {
+                            app.toggle_device_filter(DeviceFilter::Services);
+                        }
+                        Some(Action::Anonymize) => {
+                            app.anonymize_display = !app.anonymize_display;
+                        }
+                        Some(Action::Freeze) => {
+                            app.toggle_display_frozen();
+                        }
+                        Some(Action::Favorite) => {
+                            app.toggle_favorite();
+                        }
+                        Some(Action::FavoritesOnly) => {
+                            app.toggle_favorites_only();
+                        }
+                        Some(Action::ManufacturerAscii) => {
+                            app.manufacturer_ascii = !app.manufacturer_ascii;
+                        }
+                        Some(Action::Payload) => {
+                            app.payload_view = !app.payload_view;
+                            app.payload_scroll = 0;
+                        }
+                        Some(Action::ShortUuids) => {
+                            app.short_uuids = !app.short_uuids;
+                        }
+                        Some(Action::Sort) => {
+                            app.cycle_sort_key();
+                        }
+                        Some(Action::SortDirection) => {
+                            app.toggle_sort_direction();
+                        }
+                        Some(Action::Note) => {
+                            app.edit_note();
+                        }
+                        Some(Action::Reconnect) => {
+                            app.reconnect_last();
+                        }
+                        Some(Action::RestartScan) => {
+                            app.restart_scan().await;
+                        }
+                        Some(Action::ConnectAddress) => {
+                            app.start_editing(InputTarget::ConnectAddress);
+                        }
+                        Some(Action::GoTo) => {
+                            app.start_editing(InputTarget::GoTo);
+                        }
+                        Some(Action::NameFilter) => {
+                            app.edit_name_filter();
+                        }
+                        Some(Action::LogFilter) => {
+                            app.edit_log_filter();
+                        }
+                        Some(Action::Read) => {
+                            app.read_selected_characteristic(false);
+                        }
+                        Some(Action::AutoRead) => {
+                            app.toggle_auto_read();
+                        }
+                        Some(Action::Refresh) => {
+                            app.read_selected_characteristic(true);
+                        }
+                        Some(Action::Write) => {
+                            app.start_write(false);
+                        }
+                        Some(Action::WriteType) => {
+                            app.toggle_write_type();
+                        }
+                        Some(Action::Verify) => {
+                            app.start_write(true);
+                        }
+                        Some(Action::Notify) => {
+                            app.toggle_notify_selected_characteristic();
+                        }
+                        Some(Action::Record) => {
+                            app.error_message = match app.toggle_recording() {
+                                Ok(message) => message,
+                                Err(e) => e.to_string(),
                             };
-                            app.table_state.select(Some(next));
+                            app.error_view = true;
                         }
-                    }
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if app.inspect_view {
-                            app.inspect_overlay_scroll =
-                                app.inspect_overlay_scroll.saturating_sub(1);
-                        } else {
-                            let previous = match app.table_state.selected() {
-                                Some(selected) => {
-                                    if selected == 0 {
-                                        app.devices.len() - 1
-                                    } else {
-                                        selected - 1
-                                    }
-                                }
-                                None => 0,
+                        Some(Action::Export) => {
+                            app.error_message = match app.get_devices_csv() {
+                                Ok(success_message) => success_message,
+                                Err(e) => e.to_string(),
+                            };
+                            app.error_view = true;
+                        }
+                        Some(Action::ExportSelected) => {
+                            app.error_message = match app.get_selected_devices_csv() {
+                                Ok(success_message) => success_message,
+                                Err(e) => e.to_string(),
                             };
-                            app.table_state.select(Some(previous));
+                            app.error_view = true;
+                        }
+                        Some(Action::Copy) => {
+                            app.error_message = match app.copy_visible_devices() {
+                                Ok(message) => message,
+                                Err(e) => e.to_string(),
+                            };
+                            app.error_view = true;
+                        }
+                        Some(Action::Select) => {
+                            app.toggle_selected();
+                        }
+                        Some(Action::SelectAll) => {
+                            app.select_all();
+                        }
+                        Some(Action::ClearSelection) => {
+                            app.selected_ids.clear();
+                        }
+                        Some(Action::Connections) => {
+                            app.connections_view = !app.connections_view;
+                            app.connections_selected = 0;
+                        }
+                        Some(Action::Disconnect) => {
+                            app.disconnect_selected();
+                        }
+                        Some(Action::Alert) => {
+                            app.alert_on_match = !app.alert_on_match;
+                        }
+                        Some(Action::Locate) => {
+                            app.toggle_locate();
+                        }
+                        Some(Action::ServicePicker) => {
+                            app.open_service_picker();
+                        }
+                        Some(Action::DenseLayout) => {
+                            app.toggle_dense_layout();
                         }
+                        Some(Action::Details) if app.dense_layout => {
+                            app.details_view = !app.details_view;
+                        }
+                        Some(Action::RawProperties) => {
+                            if app.raw_properties_view {
+                                app.raw_properties_view = false;
+                            } else if app.selected_device().is_some() {
+                                app.refresh_properties().await;
+                                app.raw_properties_scroll = 0;
+                                app.raw_properties_view = true;
+                            }
+                        }
+                        Some(Action::Connect) => {
+                            if app.error_view {
+                                app.error_view = false;
+                            } else if app.connections_view {
+                                app.connections_view = false;
+                            } else if app.raw_properties_view {
+                                app.raw_properties_view = false;
+                            } else if app.payload_view {
+                                app.payload_view = false;
+                            } else if app.inspect_view {
+                                app.inspect_view = false;
+                            } else if app.details_view {
+                                app.details_view = false;
+                            } else {
+                                app.connect().await;
+                            }
+                        }
+                        Some(Action::NavDown) => {
+                            if app.connections_view {
+                                if app.connections_selected + 1 < app.connections.len() {
+                                    app.connections_selected += 1;
+                                }
+                            } else if app.raw_properties_view {
+                                app.raw_properties_scroll += 1;
+                            } else if app.payload_view {
+                                app.payload_scroll = app.payload_scroll.saturating_add(1);
+                            } else if app.inspect_view {
+                                app.move_characteristic_selection(1);
+                            } else if app.details_view || app.focus == Focus::Details {
+                                app.detail_scroll += 1;
+                            } else if app.focus == Focus::Log {
+                                app.scroll_log(-1);
+                            } else if !app.visible_indices().is_empty() {
+                                let count = app.visible_indices().len();
+                                let next = match app.table_state.selected() {
+                                    Some(selected) => {
+                                        if selected >= count - 1 {
+                                            0
+                                        } else {
+                                            selected + 1
+                                        }
+                                    }
+                                    None => 0,
+                                };
+                                app.table_state.select(Some(next));
+                            }
+                        }
+                        Some(Action::NavUp) => {
+                            if app.connections_view {
+                                app.connections_selected =
+                                    app.connections_selected.saturating_sub(1);
+                            } else if app.raw_properties_view {
+                                app.raw_properties_scroll =
+                                    app.raw_properties_scroll.saturating_sub(1);
+                            } else if app.payload_view {
+                                app.payload_scroll = app.payload_scroll.saturating_sub(1);
+                            } else if app.inspect_view {
+                                app.move_characteristic_selection(-1);
+                            } else if app.details_view || app.focus == Focus::Details {
+                                app.detail_scroll = app.detail_scroll.saturating_sub(1);
+                            } else if app.focus == Focus::Log {
+                                app.scroll_log(1);
+                            } else if !app.visible_indices().is_empty() {
+                                let count = app.visible_indices().len();
+                                let previous = match app.table_state.selected() {
+                                    Some(selected) => {
+                                        if selected == 0 {
+                                            count - 1
+                                        } else {
+                                            selected - 1
+                                        }
+                                    }
+                                    None => 0,
+                                };
+                                app.table_state.select(Some(previous));
+                            }
+                        }
+                        Some(Action::PropertyFilter) => {
+                            app.cycle_property_filter();
+                        }
+                        Some(Action::PropertiesHex) => {
+                            app.props_hex = !app.props_hex;
+                        }
+                        Some(Action::NusTerminal) if app.has_nus() => {
+                            app.open_nus_terminal();
+                        }
+                        _ => {}
                     }
-                    _ => {}
                 }
             }
         }
 
         // Check for updates
-        if let Ok(new_device) = app.rx.try_recv() {
-            match new_device {
-                DeviceData::DeviceInfo(device) => app.devices.push(device),
-                DeviceData::Characteristics(characteristics) => {
-                    app.selected_characteristics = characteristics;
-                    app.inspect_view = true;
-                    app.is_loading = false;
-                }
-                DeviceData::Error(error) => {
-                    app.error_message = error;
-                    app.error_view = true;
-                    app.is_loading = false;
-                }
+        let mut updates = Vec::new();
+        while updates.len() < MAX_UPDATES_PER_TICK {
+            match app.rx.try_recv() {
+                Ok(data) => updates.push(data),
+                Err(_) => break,
             }
+        }
+        backlog = updates.len() == MAX_UPDATES_PER_TICK;
+        if !updates.is_empty() {
+            app.last_activity = Instant::now();
+            app.dirty = true;
+            app.on_device_updates(updates);
 
             if app.table_state.selected().is_none() {
                 app.table_state.select(Some(0));
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use ratatui::{
+    style::{Color, Style},
+    widgets::{Block, Borders, Gauge},
+};
+
+/// Creates a gauge that fills up as a connection attempt approaches its timeout, counting down
+/// the seconds left before it gives up.
+pub fn connect_gauge(elapsed: Duration, timeout: Duration) -> Gauge<'static> {
+    let ratio = if timeout.is_zero() {
+        1.0
+    } else {
+        (elapsed.as_secs_f64() / timeout.as_secs_f64()).min(1.0)
+    };
+
+    Gauge::default()
+        .block(
+            Block::default()
+                .title("Connecting [esc → cancel]")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .gauge_style(Style::default().fg(Color::Yellow))
+        .ratio(ratio)
+        .label(format!(
+            "Connecting... {}s left",
+            // Round up, so the countdown only reaches 0 when it gives up
+            timeout.saturating_sub(elapsed).as_secs_f64().ceil() as u64
+        ))
+}
@@ -0,0 +1,79 @@
+use std::collections::hash_map::RandomState;
+
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Row, Table},
+};
+
+use crate::{
+    structs::ConnectionState,
+    utils::{anonymize_address, format_duration},
+};
+
+/// Provides an overlay with every peripheral currently connected, oldest connection first.
+/// Identifiers are redacted with the `anonymize` key when there is one.
+pub fn connections_panel<'a>(
+    connections: &[&ConnectionState],
+    selected: usize,
+    anonymize: Option<&RandomState>,
+) -> Table<'a> {
+    let selected_style = Style::default().add_modifier(Modifier::REVERSED);
+    let rows: Vec<Row> = if connections.is_empty() {
+        vec![Row::new(vec!["No connected devices".to_owned()])
+            .style(Style::default().fg(Color::DarkGray))]
+    } else {
+        connections
+            .iter()
+            .enumerate()
+            .map(|(i, connection)| {
+                let style = if i == selected {
+                    selected_style
+                } else {
+                    Style::default()
+                };
+                let id = connection.device.get_id();
+                Row::new(vec![
+                    match anonymize {
+                        Some(key) => anonymize_address(&id, key),
+                        None => id,
+                    },
+                    format_duration(connection.connected_at.elapsed()),
+                    connection
+                        .mtu
+                        .map_or_else(|| "n/a".to_owned(), |mtu| mtu.to_string()),
+                    connection.characteristics.to_string(),
+                    connection.subscriptions.len().to_string(),
+                ])
+                .style(style)
+            })
+            .collect()
+    };
+
+    Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(10),
+            Constraint::Length(5),
+            Constraint::Length(15),
+            Constraint::Length(13),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Device",
+            "Connected",
+            "MTU",
+            "Characteristics",
+            "Subscriptions",
+        ])
+        .style(Style::default().fg(Color::Yellow)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Connections [d → disconnect]")
+            .border_style(Style::default().fg(Color::Yellow)),
+    )
+}
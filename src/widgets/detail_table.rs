@@ -1,37 +1,214 @@
 use ratatui::{
     layout::Constraint,
+    style::{Color, Style},
     widgets::{Block, Borders, Row, Table},
 };
 
-use crate::{structs::DeviceInfo, utils::extract_manufacturer_data};
+use std::time::Duration;
+
+use crate::{
+    structs::{ConnectionState, DeviceInfo},
+    utils::{
+        bytes_to_ascii, bytes_to_hex, decode_eddystone_url, decode_service_data,
+        extract_manufacturer_data, fit_bytes, format_duration, sanitize_name, service_name,
+        uuid_to_u16, EDDYSTONE_UUID,
+    },
+};
+
+const LABEL_WIDTH: u16 = 20;
+const VALUE_WIDTH: u16 = 80;
+
+/// Creates a table with more detailed information about a selected device, or a placeholder
+/// when no device is selected.
+/// `connection` is the state of the connection to the device, if it is connected.
+/// `advertising_interval` is the estimated time between its advertisements.
+/// `gatt_tx_power` is the value read from its TX Power Level characteristic, shown apart from
+/// the TX power it advertises.
+/// With `manufacturer_ascii` the manufacturer data is also shown as ASCII next to the hex.
+/// The first `scroll` rows are skipped, with `scroll` kept within the rows, and the border is
+/// highlighted when `focused`. Manufacturer data too long for the `width` of the table is
+/// truncated, telling how many bytes are left out, and a note too long for it is truncated
+/// by its display width like the columns of the device table.
+#[allow(clippy::too_many_arguments)]
+pub fn detail_table<'a>(
+    selected_device: Option<&'a DeviceInfo>,
+    connection: Option<&ConnectionState>,
+    note: Option<&String>,
+    distance: Option<f32>,
+    advertising_interval: Option<Duration>,
+    gatt_tx_power: Option<i8>,
+    manufacturer_ascii: bool,
+    scroll: &mut usize,
+    focused: bool,
+    width: u16,
+) -> Table<'a> {
+    let block = Block::default()
+        .title("More Details".to_owned())
+        .borders(Borders::ALL)
+        .border_style(if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        });
+
+    let Some(selected_device) = selected_device else {
+        return Table::new(
+            vec![Row::new(vec!["No device selected".to_owned()])
+                .style(Style::default().fg(Color::DarkGray))],
+            [Constraint::Fill(1)],
+        )
+        .block(block);
+    };
 
-/// Creates a table with more detailed information about a selected device.
-pub fn detail_table(selected_device: &DeviceInfo) -> Table {
     let services_binding = selected_device.services.len().to_string();
-    let manufacturer_data = extract_manufacturer_data(&selected_device.manufacturer_data);
-    let table = Table::new(
-        vec![
-            Row::new(vec![
-                "Detected At:".to_owned(),
-                selected_device.detected_at.clone(),
-            ]),
-            Row::new(vec!["Services:".to_owned(), services_binding]),
-            Row::new(vec![
-                "Company Code ID:".to_owned(),
-                manufacturer_data.company_code,
-            ]),
+    // The value column, within the borders and after the label column and its spacing
+    let value_width = (width.saturating_sub(2 + LABEL_WIDTH + 1)).min(VALUE_WIDTH) as usize;
+    let mut manufacturer_data = extract_manufacturer_data(&selected_device.manufacturer_data);
+    if !selected_device.manufacturer_data.is_empty() {
+        let bytes: Vec<u8> = selected_device
+            .manufacturer_data
+            .values()
+            .flatten()
+            .copied()
+            .collect();
+        manufacturer_data.data = if manufacturer_ascii {
+            fit_bytes(&bytes, value_width, |bytes| {
+                format!("{} |{}|", bytes_to_hex(bytes), bytes_to_ascii(bytes))
+            })
+        } else {
+            fit_bytes(&bytes, value_width, bytes_to_hex)
+        };
+    }
+    let mut rows = vec![
+        Row::new(vec![
+            "Detected At:".to_owned(),
+            selected_device.detected_at.clone(),
+        ]),
+        Row::new(vec![
+            "Times Seen:".to_owned(),
+            selected_device.times_seen.to_string(),
+        ]),
+        Row::new(vec!["Services:".to_owned(), services_binding]),
+        Row::new(vec![
+            "Company Code ID:".to_owned(),
+            manufacturer_data.company_code,
+        ]),
+        Row::new(vec![
+            "Manufacturer Data:".to_owned(),
+            manufacturer_data.data,
+        ]),
+    ];
+
+    let mut service_data: Vec<_> = selected_device.service_data.iter().collect();
+    service_data.sort_by_key(|(uuid, _)| **uuid);
+    let eddystone_url = service_data
+        .iter()
+        .find(|(uuid, _)| uuid_to_u16(uuid) == Some(EDDYSTONE_UUID))
+        .and_then(|(_, data)| decode_eddystone_url(data));
+    for (i, (uuid, data)) in service_data.into_iter().enumerate() {
+        let label = if i == 0 { "Service Data:" } else { "" };
+        let name = service_name(uuid).map_or_else(|| uuid.to_string(), |name| name.to_owned());
+        rows.push(Row::new(vec![
+            label.to_owned(),
+            format!("{}: {}", name, decode_service_data(uuid, data)),
+        ]));
+    }
+    if let Some(url) = eddystone_url {
+        rows.push(Row::new(vec!["Eddystone URL:".to_owned(), url]));
+    }
+
+    rows.extend([
+        Row::new(vec![
+            "RSSI:".to_owned(),
+            match selected_device.properties.rssi {
+                Some(rssi) => format!("{} dBm", rssi),
+                None => "n/a".to_owned(),
+            },
+        ]),
+        Row::new(vec![
+            "Smoothed RSSI:".to_owned(),
+            selected_device
+                .smoothed_rssi
+                .map_or_else(|| "n/a".to_owned(), |rssi| format!("{:.1} dBm", rssi)),
+        ]),
+        Row::new(vec![
+            "Adv TX Power:".to_owned(),
+            match selected_device.properties.tx_power_level {
+                Some(tx_power) => format!("{} dBm", tx_power),
+                None => "n/a".to_owned(),
+            },
+        ]),
+        Row::new(vec![
+            "GATT TX Power:".to_owned(),
+            gatt_tx_power.map_or_else(|| "n/a".to_owned(), |tx_power| format!("{} dBm", tx_power)),
+        ]),
+        Row::new(vec![
+            "Est. Distance:".to_owned(),
+            distance.map_or_else(
+                || "n/a".to_owned(),
+                |distance| format!("~{:.1} m (estimate)", distance),
+            ),
+        ]),
+        Row::new(vec![
+            "Adv. Interval:".to_owned(),
+            advertising_interval.map_or_else(
+                || "n/a".to_owned(),
+                |interval| format!("~{} ms", interval.as_millis()),
+            ),
+        ]),
+        Row::new(vec![
+            "Connection:".to_owned(),
+            connection.map_or_else(
+                || "not connected".to_owned(),
+                |connection| {
+                    format!(
+                        "connected {}",
+                        format_duration(connection.connected_at.elapsed())
+                    )
+                },
+            ),
+        ]),
+        Row::new(vec![
+            "Note:".to_owned(),
+            note.map_or_else(|| "n/a".to_owned(), |note| sanitize_name(note, value_width)),
+        ]),
+    ]);
+
+    if let Some(connection) = connection {
+        let or_na = |value: Option<String>| value.unwrap_or_else(|| "n/a".to_owned());
+        // Shown under the connection row, above the note
+        rows.insert(
+            rows.len() - 1,
             Row::new(vec![
-                "Manufacturer Data:".to_owned(),
-                manufacturer_data.data,
+                "Conn. Params:".to_owned(),
+                format!(
+                    "interval {}, latency {}, timeout {}",
+                    or_na(
+                        connection
+                            .interval
+                            .map(|interval| format!("{:.2} ms", interval.as_secs_f32() * 1000.0))
+                    ),
+                    or_na(connection.latency.map(|latency| latency.to_string())),
+                    or_na(
+                        connection
+                            .supervision_timeout
+                            .map(|timeout| format!("{} ms", timeout.as_millis()))
+                    ),
+                ),
             ]),
+        );
+    }
+
+    *scroll = (*scroll).min(rows.len().saturating_sub(1));
+    rows.drain(..*scroll);
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(LABEL_WIDTH),
+            Constraint::Length(VALUE_WIDTH),
         ],
-        [Constraint::Length(20), Constraint::Length(80)],
     )
-    .block(
-        Block::default()
-            .title("More Details".to_owned())
-            .borders(Borders::ALL),
-    );
+    .block(block);
 
     table
 }
@@ -1,14 +1,53 @@
 use ratatui::{
     layout::Constraint,
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Row, Table},
+    widgets::{Block, Borders, Cell, Row, Table},
 };
 
-use crate::structs::DeviceInfo;
+use std::collections::{hash_map::RandomState, HashSet};
 
-/// Creates a table with the detected BTLE devices.
-pub fn device_table(selected: Option<usize>, devices: &[DeviceInfo]) -> Table {
+use crate::{
+    app::SortKey,
+    structs::{Column, DeviceInfo},
+    utils::{anonymize_address, sanitize_name},
+};
+
+/// Creates a table with the detected BTLE devices, showing `columns` in order. Favorite devices
+/// are marked with a star, and devices selected for a bulk export with a checkmark. The header
+/// marks the sorted column with the direction of the sort. Cells that `changed` recently are
+/// highlighted in bold. The title tells when the display is `frozen`. Identifiers are redacted
+/// with the `anonymize` key when there is one, and the border is highlighted when the table is `focused`, or
+/// flashes when `flash` is set.
+#[allow(clippy::too_many_arguments)]
+pub fn device_table<'a>(
+    selected: Option<usize>,
+    devices: &[&'a DeviceInfo],
+    favorites: &HashSet<String>,
+    selected_ids: &HashSet<String>,
+    columns: &[Column],
+    distance: impl Fn(&DeviceInfo) -> Option<f32>,
+    changed: impl Fn(&DeviceInfo, Column) -> bool,
+    sort_key: SortKey,
+    sort_ascending: bool,
+    frozen: bool,
+    anonymize: Option<&RandomState>,
+    focused: bool,
+    flash: bool,
+) -> Table<'a> {
     let selected_style = Style::default().add_modifier(Modifier::REVERSED);
+    let header = |column: &Column| {
+        let key = match column {
+            Column::Name => Some(SortKey::Name),
+            Column::TxPower => Some(SortKey::TxPower),
+            Column::Rssi => Some(SortKey::Rssi),
+            _ => None,
+        };
+        match (key == Some(sort_key), sort_ascending) {
+            (true, true) => format!("{} ▲", column.title()),
+            (true, false) => format!("{} ▼", column.title()),
+            (false, _) => column.title().to_owned(),
+        }
+    };
     let rows: Vec<Row> = devices
         .iter()
         .enumerate()
@@ -18,35 +57,66 @@ pub fn device_table(selected: Option<usize>, devices: &[DeviceInfo]) -> Table {
             } else {
                 Style::default()
             };
-            Row::new(vec![
-                device.get_id(),
-                device.name.clone(),
-                device.tx_power.clone(),
-                device.rssi.clone(),
-            ])
-            .style(style)
+            let star = if favorites.contains(&device.id) {
+                "★"
+            } else {
+                ""
+            };
+            let check = if selected_ids.contains(&device.id) {
+                "✓"
+            } else {
+                ""
+            };
+            let mut cells = vec![Cell::from(check), Cell::from(star)];
+            let distance = distance(device);
+            cells.extend(columns.iter().map(|column| {
+                let value = match (column, anonymize) {
+                    (Column::Identifier, Some(key)) => anonymize_address(&device.get_id(), key),
+                    _ => column.value(device, distance),
+                };
+                let cell = Cell::from(sanitize_name(&value, column.width() as usize));
+                if changed(device, *column) {
+                    cell.style(Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    cell
+                }
+            }));
+            Row::new(cells).style(style)
         })
         .collect();
 
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(40),
-            Constraint::Length(30),
-            Constraint::Length(10),
-            Constraint::Length(10),
-        ],
-    )
-    .header(
-        Row::new(vec!["Identifier", "Name", "TX Power", "RSSI"])
-            .style(Style::default().fg(Color::Yellow)),
-    )
-    .block(
-        Block::default()
-            .title("Detected Devices")
-            .borders(Borders::ALL),
-    )
-    .highlight_style(selected_style);
+    let mut widths = vec![Constraint::Length(1), Constraint::Length(1)];
+    widths.extend(
+        columns
+            .iter()
+            .map(|column| Constraint::Length(column.width())),
+    );
+    let mut titles = vec![String::new(), String::new()];
+    titles.extend(columns.iter().map(header));
+
+    let table = Table::new(rows, widths)
+        .header(Row::new(titles).style(Style::default().fg(Color::Yellow)))
+        .block(
+            Block::default()
+                .title(
+                    match (frozen, sort_key == SortKey::Detected && !sort_ascending) {
+                        (true, _) => "Detected Devices (display frozen, z to resume)",
+                        (false, true) => "Detected Devices (newest first)",
+                        (false, false) => "Detected Devices",
+                    },
+                )
+                .borders(Borders::ALL)
+                .border_style(if flash {
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD)
+                } else if focused {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                }),
+        )
+        .highlight_style(selected_style);
 
     table
 }
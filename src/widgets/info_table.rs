@@ -1,15 +1,31 @@
 use ratatui::{
     layout::Constraint,
     style::{Color, Style},
-    widgets::{Row, Table},
+    widgets::{Cell, Row, Table},
 };
 
 /// Creates a table with information about the application and the user input.
-pub fn info_table(signal: bool, is_loading: &bool, frame_count: &usize) -> Table<'static> {
+/// `dropped_events` is the number of advertisements dropped because the UI fell behind, and
+/// `evicted_devices` the number of devices removed to stay within the device limit, and
+/// `duplicate_discoveries` the number of advertisements updating a device already listed.
+/// `filters` are the labels of the filters applied to the device table, and `scan_services`
+/// those of the services scanned for.
+#[allow(clippy::too_many_arguments)]
+pub fn info_table(
+    signal: bool,
+    is_loading: &bool,
+    frame_count: &usize,
+    dropped_events: usize,
+    evicted_devices: usize,
+    duplicate_discoveries: usize,
+    is_recording: bool,
+    filters: &[&str],
+    scan_services: &[String],
+) -> Table<'static> {
     let spinner = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
     let index = frame_count % spinner.len();
-    let info_text = format!(
-        "[q → exit] [e → export csv] [up/down → navigate] [enter → open/close] {}",
+    let mut info_text = format!(
+        "[q → exit] [e → export csv] [r → record] [up/down → navigate] [enter → open/close] {}",
         if *is_loading {
             format!("[loading... {}]", spinner[index])
         } else if signal {
@@ -18,9 +34,35 @@ pub fn info_table(signal: bool, is_loading: &bool, frame_count: &usize) -> Table
             "[s → stop scan]".to_string()
         }
     );
+    if dropped_events > 0 {
+        info_text.push_str(&format!(" [dropped: {}]", dropped_events));
+    }
+    if evicted_devices > 0 {
+        info_text.push_str(&format!(" [evicted: {}]", evicted_devices));
+    }
+    if duplicate_discoveries > 0 {
+        info_text.push_str(&format!(" [duplicates: {}]", duplicate_discoveries));
+    }
+    if !filters.is_empty() {
+        info_text.push_str(&format!(" [filters: {}]", filters.join(", ")));
+    }
 
-    let info_row = vec![Row::new(vec![info_text]).style(Style::default().fg(Color::DarkGray))];
-    let table = Table::new(info_row, [Constraint::Fill(1)]).column_spacing(1);
+    if !scan_services.is_empty() {
+        info_text.push_str(&format!(" [scan: {}]", scan_services.join(", ")));
+    }
+
+    let recording_text = if is_recording { "● REC" } else { "" };
+    let info_row = vec![Row::new(vec![
+        Cell::from(recording_text).style(Style::default().fg(Color::Red)),
+        Cell::from(info_text),
+    ])
+    .style(Style::default().fg(Color::DarkGray))];
+    let recording_width = if is_recording { 5 } else { 0 };
+    let table = Table::new(
+        info_row,
+        [Constraint::Length(recording_width), Constraint::Fill(1)],
+    )
+    .column_spacing(1);
 
     table
 }
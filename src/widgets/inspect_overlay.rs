@@ -1,23 +1,90 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
+use btleplug::api::WriteType;
 use ratatui::{
     layout::Constraint,
     style::{Color, Modifier, Style},
     widgets::{Block, Borders, Row, Table},
 };
 
-use crate::structs::Characteristic;
+use uuid::Uuid;
+
+use crate::{
+    structs::{Characteristic, PropertyFilter},
+    utils::{
+        bytes_to_ascii, bytes_to_hex, props_to_hex, service_name, short_uuid, uuid_to_u16,
+        DEVICE_INFORMATION,
+    },
+};
 
-/// Provides an overlay with the selected device's services.
+/// The width of the line separating services, which is clipped to the overlay.
+const SEPARATOR_WIDTH: usize = 200;
+
+/// Provides an overlay with the selected device's services. Characteristics in `subscriptions`
+/// are marked as subscribed, or indicating when they only support indications.
+/// Returns the overlay along with its total number of rows. `characteristics` have already been
+/// filtered by `filter`, which is shown in the title, and are grouped under a header naming
+/// each service, with a separator line between the groups. Only characteristics are selectable.
+/// The `selected` characteristic is highlighted, and `scroll` is adjusted to keep it in view.
+/// Each characteristic shows its last known value from `values`, with how long ago it was read
+/// or notified from `values_at`. Strings read from the Device Information
+/// Service are listed first. With `short_uuids`, UUIDs assigned by the Bluetooth SIG are shown
+/// in their short form, and with `props_hex` properties are shown with their bitmask. Writable characteristics show how they are written, without response
+/// for those in `without_response`. The title tells whether characteristics are read as they
+/// are highlighted, with `auto_read`.
+#[allow(clippy::too_many_arguments)]
 pub fn inspect_overlay(
-    characteristics: &[Characteristic],
-    scroll: usize,
+    characteristics: &[&Characteristic],
+    filter: PropertyFilter,
+    subscriptions: Option<&HashSet<Uuid>>,
+    values: &HashMap<Uuid, Vec<u8>>,
+    values_at: &HashMap<Uuid, Instant>,
+    without_response: &HashSet<Uuid>,
+    selected: usize,
+    scroll: &mut usize,
     height: u16,
-) -> Table<'static> {
+    short_uuids: bool,
+    props_hex: bool,
+    auto_read: bool,
+) -> (Table<'static>, usize) {
+    let format_uuid = |uuid: &Uuid| {
+        if short_uuids {
+            short_uuid(uuid)
+        } else {
+            uuid.to_string()
+        }
+    };
     let mut rows: Vec<Row> = Vec::new();
+    let mut index = 0;
+    let mut selected_row = 0;
     let mut services: HashMap<String, Vec<&Characteristic>> = HashMap::new();
 
-    for characteristic in characteristics.iter() {
+    let device_information: Vec<Row> = DEVICE_INFORMATION
+        .iter()
+        .filter_map(|(short, label)| {
+            let (_, value) = values
+                .iter()
+                .find(|(uuid, _)| uuid_to_u16(uuid) == Some(*short))?;
+            let value = String::from_utf8_lossy(value);
+            Some(Row::new(vec![format!(
+                "  ↳ {}: {}",
+                label,
+                value.trim_end_matches('\0')
+            )]))
+        })
+        .collect();
+    if !device_information.is_empty() {
+        rows.push(
+            Row::new(vec!["Device Information"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+        rows.extend(device_information);
+    }
+
+    for &characteristic in characteristics.iter() {
         let service_uuid = characteristic.service.to_string();
         services
             .entry(service_uuid)
@@ -29,13 +96,30 @@ pub fn inspect_overlay(
     sorted_services.sort_by_key(|(uuid, _)| uuid.clone());
 
     for (service_uuid, characteristics) in sorted_services {
+        let service = match characteristics.first() {
+            Some(characteristic) => match service_name(&characteristic.service) {
+                Some(name) => format!("{} ({})", name, format_uuid(&characteristic.service)),
+                None => format_uuid(&characteristic.service),
+            },
+            None => service_uuid,
+        };
+        // Separate the service from the one or the Device Information above it
+        if !rows.is_empty() {
+            rows.push(
+                Row::new(vec!["─".repeat(SEPARATOR_WIDTH)])
+                    .style(Style::default().fg(Color::DarkGray)),
+            );
+        }
         rows.push(
-            Row::new(vec![format!("Service: {service_uuid}")])
-                .style(Style::default().add_modifier(Modifier::BOLD)),
+            Row::new(vec![format!("Service: {service}")]).style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
         );
 
         for characteristic in characteristics {
-            let properties = format!(
+            let mut properties = format!(
                 "{:?}",
                 characteristic
                     .properties
@@ -44,17 +128,64 @@ pub fn inspect_overlay(
                     .collect::<Vec<String>>()
                     .join(", ")
             );
+            if props_hex {
+                properties = format!("{} {}", props_to_hex(characteristic.properties), properties);
+            }
 
-            rows.push(Row::new(vec![format!(
-                "  ↳ Characteristic: {}",
-                characteristic.uuid.to_string()
-            )]));
+            let style = if index == selected {
+                selected_row = rows.len();
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            index += 1;
+            rows.push(
+                Row::new(vec![format!(
+                    "  ↳ Characteristic: {}",
+                    format_uuid(&characteristic.uuid)
+                )])
+                .style(style),
+            );
             rows.push(Row::new(vec![format!("    ↳ Properties: {}", properties)]));
+            if let Some(value) = values.get(&characteristic.uuid) {
+                let age = values_at
+                    .get(&characteristic.uuid)
+                    .map_or_else(String::new, |at| {
+                        format!(" ({}s ago)", at.elapsed().as_secs())
+                    });
+                rows.push(Row::new(vec![format!(
+                    "    ↳ Value: {} |{}|{}",
+                    bytes_to_hex(value),
+                    bytes_to_ascii(value),
+                    age
+                )]));
+            }
+            if let Some(write_type) =
+                characteristic.write_type(without_response.contains(&characteristic.uuid))
+            {
+                let write_type = match write_type {
+                    WriteType::WithResponse => "with response",
+                    WriteType::WithoutResponse => "without response",
+                };
+                rows.push(Row::new(vec![if characteristic.has_write_choice() {
+                    format!("    ↳ Write: {} [W → toggle]", write_type)
+                } else {
+                    format!("    ↳ Write: {}", write_type)
+                }]));
+            }
+            if subscriptions.is_some_and(|s| s.contains(&characteristic.uuid)) {
+                if let Some(label) = characteristic.subscription_label() {
+                    rows.push(
+                        Row::new(vec![format!("    ↳ {}", label)])
+                            .style(Style::default().fg(Color::Green)),
+                    );
+                }
+            }
 
             for descriptor in characteristic.descriptors.iter() {
                 rows.push(Row::new(vec![format!(
                     "    ↳ Descriptor: {}",
-                    descriptor.to_string()
+                    format_uuid(descriptor)
                 )]));
             }
         }
@@ -63,7 +194,12 @@ pub fn inspect_overlay(
     let adjusted_height = if height > 3 { height - 3 } else { height };
     let visible_rows_count = adjusted_height as usize;
     let total_rows = rows.len();
-    let start_index = scroll;
+    if selected_row < *scroll {
+        *scroll = selected_row;
+    } else if selected_row >= *scroll + visible_rows_count {
+        *scroll = selected_row + 1 - visible_rows_count;
+    }
+    let start_index = *scroll;
     let end_index = usize::min(start_index + visible_rows_count, total_rows);
     let visible_rows = if start_index < total_rows {
         &rows[start_index..end_index]
@@ -71,12 +207,78 @@ pub fn inspect_overlay(
         &[]
     };
 
-    Table::new(visible_rows.to_vec(), [Constraint::Percentage(100)])
+    let table = Table::new(visible_rows.to_vec(), [Constraint::Percentage(100)])
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Characteristics")
+                .title(format!(
+                    "Characteristics ({}) [r → read] [R → refresh] [a → auto-read: {}] [w → write] [v → verify] [n → notify] [p → filter] [x → hex props] [u → uuids]",
+                    filter.label(),
+                    if auto_read { "on" } else { "off" }
+                ))
                 .border_style(Style::default().fg(Color::Yellow)),
         )
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+    (table, total_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use btleplug::api::CharPropFlags;
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+    use super::*;
+    use crate::structs::{NUS_RX_UUID, NUS_SERVICE_UUID, NUS_TX_UUID};
+
+    fn characteristic(uuid: Uuid, properties: CharPropFlags) -> Characteristic {
+        Characteristic {
+            uuid,
+            properties,
+            descriptors: Vec::new(),
+            service: NUS_SERVICE_UUID,
+        }
+    }
+
+    /// Renders the overlay and returns the text shown.
+    fn render(characteristics: &[&Characteristic], subscriptions: &HashSet<Uuid>) -> String {
+        let (table, _) = inspect_overlay(
+            characteristics,
+            PropertyFilter::All,
+            Some(subscriptions),
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashSet::new(),
+            0,
+            &mut 0,
+            20,
+            false,
+            false,
+            false,
+        );
+        let area = Rect::new(0, 0, 120, 20);
+        let mut buffer = Buffer::empty(area);
+        table.render(area, &mut buffer);
+        buffer.content.iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn subscribed_characteristics_are_marked() {
+        let tx = characteristic(NUS_TX_UUID, CharPropFlags::NOTIFY);
+        let rx = characteristic(NUS_RX_UUID, CharPropFlags::INDICATE);
+        let text = render(&[&tx, &rx], &HashSet::from([NUS_TX_UUID]));
+        assert_eq!(text.matches("Subscribed ●").count(), 1);
+        assert!(!text.contains("Indicating ●"));
+
+        let text = render(&[&tx, &rx], &HashSet::from([NUS_RX_UUID]));
+        assert!(!text.contains("Subscribed ●"));
+        assert_eq!(text.matches("Indicating ●").count(), 1);
+    }
+
+    #[test]
+    fn unsubscribed_characteristics_are_not_marked() {
+        let tx = characteristic(NUS_TX_UUID, CharPropFlags::NOTIFY);
+        let text = render(&[&tx], &HashSet::new());
+        assert!(!text.contains('●'));
+    }
 }
@@ -0,0 +1,30 @@
+use ratatui::{
+    style::{Color, Style},
+    widgets::{Block, Borders, Gauge},
+};
+
+/// Creates a gauge for a 0-100% level, such as a battery level. Values above 100 are clamped
+/// and flagged as out of range, since the device reported something that isn't a percentage.
+pub fn level_gauge(label: &str, value: u8) -> Gauge<'static> {
+    let percent = value.min(100);
+    let (text, color) = if value > 100 {
+        (
+            format!("{}% (out of range: {})", percent, value),
+            Color::Red,
+        )
+    } else if percent <= 20 {
+        (format!("{}%", percent), Color::Yellow)
+    } else {
+        (format!("{}%", percent), Color::Green)
+    };
+
+    Gauge::default()
+        .block(
+            Block::default()
+                .title(label.to_owned())
+                .borders(Borders::ALL),
+        )
+        .gauge_style(Style::default().fg(color))
+        .percent(percent as u16)
+        .label(text)
+}
@@ -0,0 +1,50 @@
+use ratatui::{
+    style::{Color, Style},
+    widgets::{Block, Borders, Gauge},
+};
+
+use crate::{
+    structs::Column,
+    utils::{sanitize_name, signal_percent},
+};
+
+/// Creates a large gauge for locating a device named `name`, filling up as its `rssi` grows
+/// stronger and turning from blue to red as it gets closer. `trend` is how much the RSSI
+/// changed lately, telling whether the device is getting hotter or colder.
+pub fn locate_gauge(
+    name: &str,
+    rssi: Option<f32>,
+    trend: Option<f32>,
+    beep: bool,
+) -> Gauge<'static> {
+    let title = format!(
+        "Locating {} [b → beep {}] [esc → close]",
+        sanitize_name(name, Column::Name.width() as usize),
+        if beep { "off" } else { "on" }
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    let Some(rssi) = rssi else {
+        return Gauge::default().block(block).percent(0).label("No signal");
+    };
+
+    let percent = signal_percent(rssi);
+    let color = match percent {
+        70.. => Color::Red,
+        40..=69 => Color::Yellow,
+        _ => Color::Blue,
+    };
+    // Changes within a dB are left out as noise
+    let trend = match trend {
+        Some(trend) if trend >= 1.0 => " hotter ▲",
+        Some(trend) if trend <= -1.0 => " colder ▼",
+        _ => "",
+    };
+    Gauge::default()
+        .block(block)
+        .gauge_style(Style::default().fg(color))
+        .percent(percent)
+        .label(format!("{:.0} dBm{}", rssi, trend))
+}
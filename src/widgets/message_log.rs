@@ -0,0 +1,50 @@
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Style},
+    widgets::{Block, Borders, Row, Table},
+};
+
+use crate::structs::{LogDirection, LogEntry};
+
+/// Creates a table with the entries of the message log, ending `scroll` entries before the most
+/// recent one. The title shows the `filter` the entries were picked with, when there is one,
+/// and the border is highlighted when the log is `focused`.
+pub fn message_log<'a>(
+    logs: &[&'a LogEntry],
+    filter: &str,
+    height: u16,
+    scroll: usize,
+    focused: bool,
+) -> Table<'a> {
+    let visible_rows_count = height.saturating_sub(2) as usize;
+    let end_index = logs.len().saturating_sub(scroll);
+    let start_index = end_index.saturating_sub(visible_rows_count);
+
+    let rows: Vec<Row> = logs[start_index..end_index]
+        .iter()
+        .map(|entry| {
+            let style = match entry.direction {
+                LogDirection::Info => Style::default(),
+                LogDirection::Error => Style::default().fg(Color::Red),
+                LogDirection::Sent => Style::default().fg(Color::Cyan),
+                LogDirection::Received => Style::default().fg(Color::Green),
+            };
+            Row::new(vec![entry.timestamp.clone(), entry.message.clone()]).style(style)
+        })
+        .collect();
+
+    Table::new(rows, [Constraint::Length(8), Constraint::Fill(1)]).block(
+        Block::default()
+            .title(if filter.is_empty() {
+                "Message Log".to_owned()
+            } else {
+                format!("Message Log (filter: {})", filter)
+            })
+            .borders(Borders::ALL)
+            .border_style(if focused {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            }),
+    )
+}
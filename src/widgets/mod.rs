@@ -1,4 +1,15 @@
+pub mod connect_gauge;
+pub mod connections_panel;
 pub mod detail_table;
 pub mod device_table;
 pub mod info_table;
 pub mod inspect_overlay;
+pub mod level_gauge;
+pub mod locate_gauge;
+pub mod message_log;
+pub mod nus_terminal;
+pub mod payload_overlay;
+pub mod raw_properties_overlay;
+pub mod scrollbar;
+pub mod service_picker;
+pub mod value_editor;
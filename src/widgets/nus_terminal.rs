@@ -0,0 +1,43 @@
+use ratatui::{
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// Provides a serial monitor style console for the Nordic UART Service. The most recent lines
+/// are kept in view, followed by the line currently being typed.
+/// `subscription` labels the subscription to the TX characteristic, e.g. "Indicating ●".
+pub fn nus_terminal<'a>(
+    lines: &'a [String],
+    input: &'a str,
+    subscription: Option<&str>,
+    height: u16,
+) -> Paragraph<'a> {
+    let visible_rows_count = height.saturating_sub(3) as usize;
+    let start_index = lines.len().saturating_sub(visible_rows_count);
+
+    let mut text: Vec<Line> = lines[start_index..]
+        .iter()
+        .map(|line| {
+            if line.starts_with("> ") {
+                Line::styled(line.as_str(), Style::default().fg(Color::DarkGray))
+            } else {
+                Line::raw(line.as_str())
+            }
+        })
+        .collect();
+    text.push(Line::styled(
+        format!("$ {}█", input),
+        Style::default().fg(Color::Yellow),
+    ));
+
+    Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Nordic UART Terminal {}[esc → close]",
+                subscription.map_or_else(String::new, |label| format!("– {} ", label))
+            ))
+            .border_style(Style::default().fg(Color::Yellow)),
+    )
+}
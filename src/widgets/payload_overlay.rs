@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{company_codes::COMPANY_CODE, utils::hex_dump};
+
+/// Provides an overlay with the full manufacturer data of a device, dumped as offset, hex and
+/// ASCII for each company. The first `scroll` lines are skipped.
+pub fn payload_overlay(manufacturer_data: &HashMap<u16, Vec<u8>>, scroll: u16) -> Paragraph<'_> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut companies: Vec<_> = manufacturer_data.iter().collect();
+    companies.sort_by_key(|(code, _)| **code);
+
+    for (code, data) in companies {
+        lines.push(Line::styled(
+            format!(
+                "0x{:04X} {} ({} bytes)",
+                code,
+                COMPANY_CODE.get(code).unwrap_or(&"n/a"),
+                data.len()
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        lines.extend(hex_dump(data).into_iter().map(Line::from));
+    }
+    if lines.is_empty() {
+        lines.push(Line::styled(
+            "No manufacturer data",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Manufacturer Data")
+            .border_style(Style::default().fg(Color::Yellow)),
+    )
+}
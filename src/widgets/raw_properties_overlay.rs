@@ -0,0 +1,118 @@
+use std::collections::hash_map::RandomState;
+
+use btleplug::api::PeripheralProperties;
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Row, Table},
+};
+
+use crate::utils::{anonymize_address, bytes_to_hex};
+
+/// Formats an optional value for display, using "n/a" when it is absent.
+fn or_na<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "n/a".to_string(), |v| v.to_string())
+}
+
+/// Provides an overlay with every field of the `PeripheralProperties` captured for a device.
+/// The address is redacted with the `anonymize` key when there is one.
+pub fn raw_properties_overlay(
+    properties: &PeripheralProperties,
+    anonymize: Option<&RandomState>,
+    scroll: usize,
+    height: u16,
+) -> Table<'static> {
+    let section_style = Style::default().add_modifier(Modifier::BOLD);
+    let mut rows: Vec<Row> = vec![
+        Row::new(vec![
+            "address".to_string(),
+            match anonymize {
+                Some(key) => anonymize_address(&properties.address.to_string(), key),
+                None => properties.address.to_string(),
+            },
+        ]),
+        Row::new(vec![
+            "address_type".to_string(),
+            properties
+                .address_type
+                .map_or_else(|| "n/a".to_string(), |t| format!("{:?}", t)),
+        ]),
+        Row::new(vec![
+            "local_name".to_string(),
+            or_na(&properties.local_name),
+        ]),
+        Row::new(vec![
+            "tx_power_level".to_string(),
+            or_na(&properties.tx_power_level),
+        ]),
+        Row::new(vec!["rssi".to_string(), or_na(&properties.rssi)]),
+        Row::new(vec!["class".to_string(), or_na(&properties.class)]),
+    ];
+
+    rows.push(
+        Row::new(vec![
+            "services".to_string(),
+            format!("({})", properties.services.len()),
+        ])
+        .style(section_style),
+    );
+    for service in properties.services.iter() {
+        rows.push(Row::new(vec![String::new(), service.to_string()]));
+    }
+
+    let mut manufacturer_data: Vec<_> = properties.manufacturer_data.iter().collect();
+    manufacturer_data.sort_by_key(|(code, _)| **code);
+    rows.push(
+        Row::new(vec![
+            "manufacturer_data".to_string(),
+            format!("({})", manufacturer_data.len()),
+        ])
+        .style(section_style),
+    );
+    for (code, data) in manufacturer_data {
+        rows.push(Row::new(vec![
+            format!("  0x{:04X}", code),
+            format!("[{}] {}", data.len(), bytes_to_hex(data)),
+        ]));
+    }
+
+    let mut service_data: Vec<_> = properties.service_data.iter().collect();
+    service_data.sort_by_key(|(uuid, _)| **uuid);
+    rows.push(
+        Row::new(vec![
+            "service_data".to_string(),
+            format!("({})", service_data.len()),
+        ])
+        .style(section_style),
+    );
+    for (uuid, data) in service_data {
+        rows.push(Row::new(vec![
+            format!("  {}", uuid),
+            format!("[{}] {}", data.len(), bytes_to_hex(data)),
+        ]));
+    }
+
+    let adjusted_height = if height > 3 { height - 3 } else { height };
+    let visible_rows_count = adjusted_height as usize;
+    let total_rows = rows.len();
+    let start_index = scroll;
+    let end_index = usize::min(start_index + visible_rows_count, total_rows);
+    let visible_rows = if start_index < total_rows {
+        &rows[start_index..end_index]
+    } else {
+        &[]
+    };
+
+    Table::new(
+        visible_rows.to_vec(),
+        [Constraint::Length(40), Constraint::Fill(1)],
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Raw Properties")
+            .border_style(Style::default().fg(Color::Yellow)),
+    )
+}
@@ -0,0 +1,25 @@
+use ratatui::{
+    layout::{Margin, Rect},
+    widgets::{Scrollbar, ScrollbarOrientation, ScrollbarState},
+    Frame,
+};
+
+/// Renders a scrollbar on the right border of `area`, showing which of the `total` rows are in
+/// view from `position` on. Nothing is rendered when every row fits in the `visible` rows.
+pub fn render_scrollbar(f: &mut Frame, area: Rect, position: usize, total: usize, visible: usize) {
+    if total <= visible {
+        return;
+    }
+    let max_position = total - visible;
+    let mut state = ScrollbarState::new(max_position)
+        .position(position.min(max_position))
+        .viewport_content_length(visible);
+    f.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight),
+        area.inner(&Margin {
+            vertical: 1,
+            horizontal: 0,
+        }),
+        &mut state,
+    );
+}
@@ -0,0 +1,47 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Row, Table},
+};
+use uuid::Uuid;
+
+use crate::utils::{service_name, short_uuid};
+
+/// Provides an overlay listing `services` to scan for, with the `chosen` ones checked. Render
+/// it with a `TableState` selecting the highlighted service, so the list scrolls to it.
+pub fn service_picker<'a>(services: &[Uuid], chosen: &HashSet<Uuid>) -> Table<'a> {
+    let rows: Vec<Row> = services
+        .iter()
+        .map(|uuid| {
+            Row::new(vec![
+                if chosen.contains(uuid) { "[x]" } else { "[ ]" }.to_owned(),
+                service_name(uuid).unwrap_or("Custom").to_owned(),
+                short_uuid(uuid),
+            ])
+        })
+        .collect();
+
+    Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Fill(1),
+            Constraint::Length(36),
+        ],
+    )
+    .header(
+        Row::new(vec!["", "Service", "UUID"]).style(Style::default().fg(Color::Yellow)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Scan for services ({}, none for all) [space → toggle] [a → add uuid] [enter → apply] [esc → cancel]",
+                chosen.len()
+            ))
+            .border_style(Style::default().fg(Color::Yellow)),
+    )
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
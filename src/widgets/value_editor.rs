@@ -0,0 +1,66 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use crate::utils::{hex_dump, hex_to_bytes};
+
+/// The longest value an attribute can hold.
+const MAX_ATTRIBUTE_LENGTH: usize = 512;
+
+/// Provides an overlay for composing the hex bytes written to a characteristic. The typed
+/// `input` is shown above whether it parses, and the bytes it parses to are dumped as hex and
+/// ASCII side by side, scrolled so the end of the dump fits the `area` of the overlay.
+pub fn value_editor(input: &str, verify: bool, area: Rect) -> Paragraph<'static> {
+    let mut lines = vec![
+        Line::styled(format!("{}█", input), Style::default().fg(Color::Yellow)),
+        Line::from(""),
+    ];
+    match hex_to_bytes(input) {
+        Ok(bytes) if bytes.len() > MAX_ATTRIBUTE_LENGTH => {
+            lines.push(Line::styled(
+                format!(
+                    "{} bytes, more than the {} an attribute can hold",
+                    bytes.len(),
+                    MAX_ATTRIBUTE_LENGTH
+                ),
+                Style::default().fg(Color::Red),
+            ));
+            lines.extend(hex_dump(&bytes).into_iter().map(Line::from));
+        }
+        Ok(bytes) if bytes.is_empty() => lines.push(Line::styled(
+            "Type or paste hex bytes, e.g. 01 0A FF",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Ok(bytes) => {
+            lines.push(Line::styled(
+                format!("{} bytes", bytes.len()),
+                Style::default().fg(Color::Green),
+            ));
+            lines.extend(hex_dump(&bytes).into_iter().map(Line::from));
+        }
+        Err(e) => lines.push(Line::styled(e, Style::default().fg(Color::Red))),
+    }
+
+    // Keep the end of the dump in view as bytes are typed, leaving room for the borders. The
+    // input wraps over as many rows as it takes, with the cursor
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let input_rows = (input.chars().count() + 1).div_ceil(width);
+    let rows = lines.len() - 1 + input_rows;
+    let scroll = (rows as u16).saturating_sub(area.height.saturating_sub(2));
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(if verify {
+                    "Write and Verify (hex) [Enter → write] [Esc → cancel]"
+                } else {
+                    "Write (hex) [Enter → write] [Esc → cancel]"
+                })
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+}